@@ -0,0 +1,1907 @@
+use std::{f64::consts::PI, iter, mem, sync::mpsc};
+
+use cgmath::{abs_diff_ne, vec2, vec4, InnerSpace, Matrix4, One, SquareMatrix, Vector2, Zero};
+use circular_buffer::CircularBuffer;
+use clap::Parser;
+use log::info;
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
+use wgpu::{util::DeviceExt, SurfaceConfiguration, TextureFormat};
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::*,
+    event_loop::{self, EventLoop, EventLoopBuilder},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowAttributes},
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::game::render::*;
+use crate::game::search::SearchParams;
+use crate::game::sgf::{sgf_geometry, SgfError};
+use crate::game::*;
+use crate::geometry::euclidian::*;
+use crate::geometry::hyperbolic::*;
+use crate::geometry::spherical::*;
+use crate::geometry::*;
+use crate::Args;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct RenderTargetVertex {
+    position: [f32; 2],
+}
+
+impl RenderTargetVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct OuterUniform {
+    f: f32,
+    skip_reprojection: u32,
+    w_scale: f32,
+    h_scale: f32,
+}
+
+impl OuterUniform {
+    fn new(skip_reprojection: bool) -> Self {
+        Self {
+            f: 1.0,
+            skip_reprojection: skip_reprojection as u32,
+            w_scale: 1.0,
+            h_scale: 1.0,
+        }
+    }
+}
+
+const RENDER_TARGET_VERTS: &[RenderTargetVertex] = &[
+    RenderTargetVertex {
+        position: [-1.0, -1.0],
+    },
+    RenderTargetVertex {
+        position: [-1.0, 1.0],
+    },
+    RenderTargetVertex {
+        position: [1.0, -1.0],
+    },
+    RenderTargetVertex {
+        position: [-1.0, 1.0],
+    },
+    RenderTargetVertex {
+        position: [1.0, -1.0],
+    },
+    RenderTargetVertex {
+        position: [1.0, 1.0],
+    },
+];
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniform {
+    transform: [[f32; 4]; 4],
+    orthographic: u32,
+    _padding: [u32; 3],
+}
+
+impl Uniform {
+    fn new(orthographic: bool) -> Self {
+        Self {
+            transform: Matrix4::identity().into(),
+            orthographic: orthographic as u32,
+            _padding: [0; 3],
+        }
+    }
+}
+
+struct InputState {
+    forward: bool,
+    left: bool,
+    right: bool,
+    back: bool,
+    cw: bool,
+    ccw: bool,
+}
+
+impl InputState {
+    fn new() -> Self {
+        InputState {
+            forward: false,
+            left: false,
+            right: false,
+            back: false,
+            cw: false,
+            ccw: false,
+        }
+    }
+
+    fn process(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state,
+                        physical_key: PhysicalKey::Code(keycode),
+                        ..
+                    },
+                ..
+            } => {
+                let is_pressed = *state == ElementState::Pressed;
+                match keycode {
+                    KeyCode::KeyW => {
+                        self.forward = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyA => {
+                        self.left = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyS => {
+                        self.back = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyD => {
+                        self.right = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyE => {
+                        self.cw = is_pressed;
+                        true
+                    }
+                    KeyCode::KeyQ => {
+                        self.ccw = is_pressed;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+fn limit_surface_res(size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+    const MAX_RES: u32 = if cfg!(target_arch = "wasm32") {
+        1 << 11
+    } else {
+        1 << 15
+    };
+
+    let max_dim = size.width.max(size.height);
+
+    if max_dim <= MAX_RES {
+        size
+    } else {
+        PhysicalSize::<u32>::from_logical(
+            size.to_logical::<f64>(1.0),
+            MAX_RES as f64 / max_dim as f64,
+        )
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_PATH: &str = "hypergo.sgf";
+
+const FONT_BYTES: &[u8] = include_bytes!("../resource/NotoSans-Regular.ttf");
+const RIGHT_TEXT_WIDTH: f32 = 420.0;
+// playouts for the ownership overlay
+const OWNERSHIP_PLAYOUTS: u32 = 200;
+
+const LABEL_FONT_SIZE: f32 = 20.0;
+const LABEL_LINE_HEIGHT: f32 = 24.0;
+// on screen spacing between neighboring points, in pixels, before the edge points
+// get labels, before every point does, and where labels are drawn at full size
+const EDGE_LABEL_SPACING: f64 = 30.0;
+const ALL_LABEL_SPACING: f64 = 90.0;
+const LABEL_SPACING: f64 = 80.0;
+const MAX_LABEL_SCALE: f64 = 1.5;
+
+// a point's name drawn next to it, in pixels
+struct PointLabel {
+    idx: i32,
+    left: f32,
+    top: f32,
+    scale: f32,
+}
+
+// total and the parts that count under the rule
+fn score_breakdown(rule: ScoringRule, name: &str, score: &PlayerScore) -> String {
+    let mut parts = match rule {
+        ScoringRule::Area => vec![
+            format!("{:} stones", score.stones),
+            format!("{:} area", score.territory),
+        ],
+        ScoringRule::Territory | ScoringRule::Aga => vec![
+            format!("{:} territory", score.territory),
+            format!("{:} prisoners", score.prisoners),
+        ],
+    };
+    if score.komi != 0.0 {
+        parts.push(format!("{:} komi", score.komi));
+    }
+    format!("\n{:}: {:}\n  {:}", name, score.total, parts.join(" + "))
+}
+
+struct TextRenderState {
+    font_system: glyphon::FontSystem,
+    swash_cache: glyphon::SwashCache,
+    viewport: glyphon::Viewport,
+    atlas: glyphon::TextAtlas,
+    text_renderer: glyphon::TextRenderer,
+    buffer_left: glyphon::Buffer,
+    buffer_right: glyphon::Buffer,
+    // one per board point
+    label_buffers: Vec<glyphon::Buffer>,
+    label_names: Vec<PointName>,
+}
+
+impl TextRenderState {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_format: TextureFormat) -> Self {
+        let mut font_system = glyphon::FontSystem::new();
+        font_system.db_mut().load_font_data(FONT_BYTES.to_vec());
+        let swash_cache = glyphon::SwashCache::new();
+        let cache = glyphon::Cache::new(&device);
+        let viewport = glyphon::Viewport::new(&device, &cache);
+        let mut atlas = glyphon::TextAtlas::new(&device, &queue, &cache, surface_format);
+        let text_renderer = glyphon::TextRenderer::new(
+            &mut atlas,
+            &device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+        let mut buffer_left =
+            glyphon::Buffer::new(&mut font_system, glyphon::Metrics::new(30.0, 42.0));
+
+        buffer_left.set_size(&mut font_system, 450.0, 800.0);
+        buffer_left.shape_until_scroll(&mut font_system, false);
+
+        let mut buffer_right =
+            glyphon::Buffer::new(&mut font_system, glyphon::Metrics::new(30.0, 42.0));
+        buffer_right.set_size(&mut font_system, RIGHT_TEXT_WIDTH, 800.0);
+        buffer_right.shape_until_scroll(&mut font_system, false);
+
+        TextRenderState {
+            font_system,
+            swash_cache,
+            viewport,
+            atlas,
+            text_renderer,
+            buffer_left,
+            buffer_right,
+            label_buffers: vec![],
+            label_names: vec![],
+        }
+    }
+
+    // only reshapes when the board changed
+    fn set_labels(&mut self, names: &[PointName]) {
+        if self.label_names == names {
+            return;
+        }
+        let attrs = glyphon::Attrs::new().family(glyphon::Family::SansSerif);
+        self.label_buffers = names
+            .iter()
+            .map(|name| {
+                let mut buffer = glyphon::Buffer::new(
+                    &mut self.font_system,
+                    glyphon::Metrics::new(LABEL_FONT_SIZE, LABEL_LINE_HEIGHT),
+                );
+                buffer.set_size(&mut self.font_system, 200.0, LABEL_LINE_HEIGHT);
+                buffer.set_text(
+                    &mut self.font_system,
+                    &name.to_string(),
+                    attrs,
+                    glyphon::Shaping::Advanced,
+                );
+                buffer.shape_until_scroll(&mut self.font_system, false);
+                buffer
+            })
+            .collect();
+        self.label_names = names.to_vec();
+    }
+
+    // unscaled width and height
+    fn label_size(&self, idx: i32) -> Vector2<f64> {
+        let width = self.label_buffers[idx as usize]
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max);
+        vec2(width as f64, LABEL_LINE_HEIGHT as f64)
+    }
+
+    fn prepare(
+        &mut self,
+        text_left: &str,
+        text_right: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        labels: &[PointLabel],
+    ) -> Result<(), glyphon::PrepareError> {
+        let attrs = glyphon::Attrs::new().family(glyphon::Family::SansSerif);
+        self.buffer_left.set_text(
+            &mut self.font_system,
+            text_left,
+            attrs,
+            glyphon::Shaping::Advanced,
+        );
+        self.buffer_right.set_text(
+            &mut self.font_system,
+            text_right,
+            attrs,
+            glyphon::Shaping::Advanced,
+        );
+        // TODO doesn't seem to render anything when setting this?
+        //self.buffer_right.lines[0].set_align(Some(glyphon::cosmic_text::Align::Right));
+
+        self.viewport.update(
+            &queue,
+            glyphon::Resolution {
+                width: config.width,
+                height: config.height,
+            },
+        );
+
+        self.text_renderer.prepare(
+            &device,
+            &queue,
+            &mut self.font_system,
+            &mut self.atlas,
+            &self.viewport,
+            labels
+                .iter()
+                .map(|label| glyphon::TextArea {
+                    buffer: &self.label_buffers[label.idx as usize],
+                    left: label.left,
+                    top: label.top,
+                    scale: label.scale,
+                    bounds: glyphon::TextBounds::default(),
+                    default_color: glyphon::Color::rgb(200, 200, 200),
+                })
+                .chain([
+                    glyphon::TextArea {
+                        buffer: &self.buffer_left,
+                        left: 10.0,
+                        top: 10.0,
+                        scale: 1.0,
+                        bounds: glyphon::TextBounds::default(),
+                        default_color: glyphon::Color::rgb(255, 255, 255),
+                    },
+                    glyphon::TextArea {
+                        buffer: &self.buffer_right,
+                        left: config.width as f32 - RIGHT_TEXT_WIDTH,
+                        top: 10.0,
+                        scale: 1.0,
+                        bounds: glyphon::TextBounds::default(),
+                        default_color: glyphon::Color::rgb(255, 255, 255),
+                    },
+                ]),
+            &mut self.swash_cache,
+        )
+    }
+
+    fn render<'pass>(
+        &'pass self,
+        render_pass: &mut wgpu::RenderPass<'pass>,
+    ) -> Result<(), glyphon::RenderError> {
+        self.text_renderer
+            .render(&self.atlas, &self.viewport, render_pass)
+    }
+
+    // TODO this actually necessary?
+    fn post_render(&mut self) {
+        self.atlas.trim();
+    }
+}
+
+struct State<SpinorT: Spinor> {
+    config: wgpu::SurfaceConfiguration,
+    size: winit::dpi::PhysicalSize<u32>,
+
+    text_render_state: TextRenderState,
+
+    outer_render_pipeline: wgpu::RenderPipeline,
+    outer_uniform: OuterUniform,
+    outer_uniform_buffer: wgpu::Buffer,
+    outer_uniform_bind_group: wgpu::BindGroup,
+
+    render_target_vertex_buffer: wgpu::Buffer,
+    render_target_pipeline: wgpu::RenderPipeline,
+    render_target_tex: wgpu::Texture,
+    render_target_tex_view: wgpu::TextureView,
+    render_target_tex_sampler: wgpu::Sampler,
+    render_target_tex_bind_group: wgpu::BindGroup,
+
+    models: Vec<Model>,
+
+    // TODO if these are going to continue using the same shader,
+    // they should share gpu buffers
+    stone_vertex_buffer: wgpu::Buffer,
+    stone_index_buffer: wgpu::Buffer,
+    stone_instances: Vec<Instance>,
+    stone_instance_buffer: wgpu::Buffer,
+
+    link_vertex_buffer: wgpu::Buffer,
+    link_index_buffer: wgpu::Buffer,
+    link_instances: Vec<Instance>,
+    link_instance_buffer: wgpu::Buffer,
+
+    uniform: Uniform,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+
+    frame_count: u64,
+    last_frame_time: Instant,
+    fps_ring: CircularBuffer<4, f64>,
+
+    input_state: InputState,
+    cursor_pos: SpinorT::Point,
+    cursor_pos_clipped: bool,
+    hover_point_pos_idx: Option<(SpinorT::Point, i32)>,
+    view_state: ViewState<SpinorT>,
+    game_state: GameState<SpinorT>,
+    // indexed by Turn
+    players: [Player; 2],
+    search_params: SearchParams,
+    bot_rng_state: u64,
+    // the position a bot is searching, keyed by tree node and turn
+    pending_move: Option<((usize, Turn), mpsc::Receiver<Move>)>,
+    show_ownership: bool,
+    show_labels: bool,
+    drag_from: Option<SpinorT::Point>,
+    last_drag_pos: SpinorT::Point,
+}
+
+impl<SpinorT: Spinor> State<SpinorT> {
+    fn new(
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_caps: &wgpu::SurfaceCapabilities,
+        tiling_parameters: TilingParameters,
+    ) -> Self {
+        let args = Args::parse();
+
+        let ms_count = if cfg!(target_arch = "wasm32") {
+            1
+        } else {
+            args.msaa
+        };
+        assert!(ms_count.count_ones() == 1);
+
+        let input_state = InputState::new();
+        let view_state = ViewState::new();
+
+        let mut game_state = if args.free_handicap {
+            GameState::with_free_handicap(tiling_parameters, args.handicap)
+        } else {
+            GameState::new(tiling_parameters, args.handicap)
+        };
+        if let Some(control) = args.time {
+            game_state.set_time_control(control);
+        }
+        let bot_rng_state = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+
+        let size = window.inner_size();
+
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+
+        let surface_size = limit_surface_res(size);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: surface_size.width,
+            height: surface_size.height,
+            //present_mode: surface_caps.present_modes[0],
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let text_render_state = TextRenderState::new(&device, &queue, surface_format);
+
+        let uniform = Uniform::new(SpinorT::project_orthographic());
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("uniform_buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("uniform_bind_group_layout"),
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("uniform_bind_group"),
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/shader.wgsl").into()),
+        });
+        let render_target_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("render_pipeline_layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let render_target_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("render_target_pipeline"),
+                layout: Some(&render_target_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::OVER,
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: ms_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+        let render_target_tex = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render_target_tex"),
+            // TODO pick a resolution more smartly
+            size: wgpu::Extent3d {
+                width: args.internal_res,
+                height: args.internal_res,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: ms_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: surface_format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let render_target_tex_view =
+            render_target_tex.create_view(&wgpu::TextureViewDescriptor::default());
+        // TODO remove?
+        let render_target_tex_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        // maybe some day try this again, but seems like i'd have to fork this to get it
+        // to work with this rendering pipeline
+        /*
+        let render_target_smaa_target = smaa::SmaaTarget::new(
+            &device,
+            &queue,
+            render_target_tex.width(),
+            render_target_tex.height(),
+            surface_format,
+            smaa::SmaaMode::Smaa1X,
+        ); */
+        let render_target_tex_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: ms_count > 1,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("render_target_tex_bind_group_layout"),
+            });
+        let render_target_tex_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &render_target_tex_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&render_target_tex_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&render_target_tex_sampler),
+                },
+            ],
+            label: Some("render_target_tex_bind_group"),
+        });
+
+        let outer_shader_src = if ms_count == 1 {
+            concat!(
+                include_str!("shaders/outer_shader_shared.wgsl"),
+                include_str!("shaders/outer_shader_noms.wgsl")
+            )
+        } else {
+            concat!(
+                include_str!("shaders/outer_shader_shared.wgsl"),
+                include_str!("shaders/outer_shader_ms.wgsl")
+            )
+        };
+        let outer_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("outer_shader"),
+            source: wgpu::ShaderSource::Wgsl(outer_shader_src.into()),
+        });
+        let outer_uniform = OuterUniform::new(SpinorT::skip_reprojection());
+        let outer_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("outer_uniform_buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let outer_uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("outer_uniform_bind_group_layout"),
+            });
+        let outer_uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &outer_uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: outer_uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("outer_uniform_bind_group"),
+        });
+        let outer_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("outer_render_pipeline_layout"),
+                bind_group_layouts: &[
+                    &render_target_tex_bind_group_layout,
+                    &outer_uniform_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let outer_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("outer_render_pipeline"),
+                layout: Some(&outer_render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &outer_shader,
+                    entry_point: "vs_main",
+                    compilation_options: Default::default(),
+                    buffers: &[RenderTargetVertex::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &outer_shader,
+                    entry_point: "fs_main",
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::REPLACE,
+                            alpha: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+
+        let render_target_vertex_buffer =
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("render_target_vertex_buffer"),
+                contents: bytemuck::cast_slice(RENDER_TARGET_VERTS),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let models = make_models::<SpinorT>(tiling_parameters.distance);
+        //info!("{:?}", models);
+
+        let stone_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("stone_vertex_buffer"),
+            contents: bytemuck::cast_slice(&models[0].verts),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let stone_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("stone_index_buffer"),
+            contents: bytemuck::cast_slice(&models[0].indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let stone_instances = Vec::new();
+        let stone_instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("stone_instance_buffer"),
+            // TODO get size from game state?
+            size: MAX_STONES * mem::size_of::<Instance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let link_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("link_vertex_buffer"),
+            contents: bytemuck::cast_slice(&models[1].verts),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let link_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("link_index_buffer"),
+            contents: bytemuck::cast_slice(&models[1].indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let link_instances = game_state.make_link_instances();
+        let link_instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("link_instance_buffer"),
+            contents: bytemuck::cast_slice(&link_instances),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            config,
+            size,
+            text_render_state,
+            outer_render_pipeline,
+            outer_uniform,
+            outer_uniform_buffer,
+            outer_uniform_bind_group,
+            render_target_vertex_buffer,
+            render_target_pipeline,
+            render_target_tex,
+            render_target_tex_view,
+            render_target_tex_sampler,
+            render_target_tex_bind_group,
+            models,
+            stone_vertex_buffer,
+            stone_index_buffer,
+            stone_instances,
+            stone_instance_buffer,
+            link_vertex_buffer,
+            link_index_buffer,
+            link_instances,
+            link_instance_buffer,
+            uniform,
+            uniform_buffer,
+            uniform_bind_group,
+            frame_count: 0,
+            last_frame_time: Instant::now(),
+            fps_ring: CircularBuffer::<4, f64>::new(),
+            input_state,
+            cursor_pos: SpinorT::Point::zero(),
+            cursor_pos_clipped: true,
+            hover_point_pos_idx: None,
+            view_state,
+            game_state,
+            players: [args.black, args.white],
+            search_params: args.search_params(),
+            bot_rng_state,
+            pending_move: None,
+            show_ownership: false,
+            show_labels: true,
+            drag_from: None,
+            last_drag_pos: SpinorT::Point::zero(),
+        }
+    }
+
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface: &wgpu::Surface,
+        new_size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            let surface_size = limit_surface_res(new_size);
+            self.config.width = surface_size.width;
+            self.config.height = surface_size.height;
+            surface.configure(device, &self.config);
+
+            // TODO skip for euclidian?
+            let ar = surface_size.width as f64 / surface_size.height as f64;
+            if ar > 1.0 {
+                self.view_state.w_scale = 1.0 / ar;
+                self.view_state.h_scale = 1.0;
+            } else {
+                self.view_state.w_scale = 1.0;
+                self.view_state.h_scale = ar;
+            }
+            self.outer_uniform.w_scale = self.view_state.w_scale as f32;
+            self.outer_uniform.h_scale = self.view_state.h_scale as f32;
+            queue.write_buffer(
+                &self.outer_uniform_buffer,
+                0,
+                bytemuck::cast_slice(&[self.outer_uniform]),
+            )
+        }
+    }
+    /*
+    fn handle_mouse(&mut self, x: f64, y: f64) {
+        //info!("handle_mouse {x} {y}");
+        if self.is_dragging {
+            // TODO
+            //self.view_state
+            //    .translate(self.view_state.pixel_delta_to_world(&self.config, x, y));
+        }
+    } */
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        if self.input_state.process(event) {
+            return true;
+        }
+        match event {
+            WindowEvent::MouseWheel { delta, .. } => {
+                let scroll_amt = match delta {
+                    MouseScrollDelta::LineDelta(_, rows) => (*rows as f64) * 100.0,
+                    MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll,
+                };
+                const SCROLL_FACTOR: f64 = 0.001;
+                self.view_state
+                    .adjust_projection_factor(scroll_amt * SCROLL_FACTOR);
+                true
+            }
+            // TODO doesn't quite work when camera moves without cursor moving
+            // can i just fetch cursor position and/or force update?
+            WindowEvent::CursorMoved { position, .. } => {
+                //info!("{:?}", position);
+                (self.cursor_pos, self.cursor_pos_clipped) = self.view_state.pixel_to_world_coords(
+                    self.size.width,
+                    self.size.height,
+                    position.x,
+                    position.y,
+                );
+                let last_hover_point_pos_idx = self.hover_point_pos_idx;
+                let checking_pos = if self.cursor_pos_clipped {
+                    None
+                } else {
+                    Some(self.cursor_pos)
+                };
+                self.hover_point_pos_idx = self.game_state.check_hover_point(checking_pos);
+                if self.hover_point_pos_idx != last_hover_point_pos_idx {
+                    self.game_state.needs_render = true;
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Left,
+                state,
+                ..
+            } => {
+                match *state {
+                    ElementState::Pressed => {
+                        if !self.cursor_pos_clipped && !self.bot_to_move() {
+                            if let Some(idx) = self.game_state.pick_point(self.cursor_pos) {
+                                self.game_state.select_point(idx);
+                            }
+                        }
+                    }
+                    ElementState::Released => (),
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                button: MouseButton::Right,
+                state,
+                ..
+            } => {
+                match *state {
+                    ElementState::Pressed => {
+                        self.drag_from = Some(self.cursor_pos);
+                    }
+                    ElementState::Released => {
+                        self.drag_from = None;
+                    }
+                }
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(keycode),
+                        ..
+                    },
+                ..
+            } => match keycode {
+                KeyCode::KeyR => {
+                    self.view_state.reset_camera();
+                    true
+                }
+                KeyCode::ArrowLeft => {
+                    self.game_state.move_history(-1);
+                    true
+                }
+                KeyCode::ArrowRight => {
+                    self.game_state.move_history(1);
+                    true
+                }
+                KeyCode::ArrowUp => {
+                    self.game_state.move_variation(-1);
+                    true
+                }
+                KeyCode::ArrowDown => {
+                    self.game_state.move_variation(1);
+                    true
+                }
+                KeyCode::KeyM => {
+                    self.game_state.go_to_mainline();
+                    true
+                }
+                KeyCode::KeyP if !self.bot_to_move() => {
+                    self.game_state.pass_move();
+                    true
+                }
+                KeyCode::KeyT => {
+                    self.game_state.toggle_scoring();
+                    true
+                }
+                KeyCode::KeyO => {
+                    self.show_ownership = !self.show_ownership;
+                    true
+                }
+                KeyCode::KeyL => {
+                    self.show_labels = !self.show_labels;
+                    true
+                }
+                KeyCode::KeyX => {
+                    let turn = self.game_state.turn();
+                    self.game_state.resign(turn);
+                    true
+                }
+                KeyCode::KeyU => {
+                    let rule = self.game_state.rules.scoring_rule.next();
+                    self.game_state.set_scoring_rule(rule);
+                    true
+                }
+                // confirms the dead stones for the player to move, then hands over
+                KeyCode::Enter if self.game_state.is_scoring() => {
+                    if let Some(score) = &self.game_state.score {
+                        let player = score.next_to_confirm(self.game_state.turn());
+                        self.game_state.confirm_score(player);
+                    }
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // stays false while looking back through the history, so moves can be undone
+    fn bot_to_move(&self) -> bool {
+        let tree = self.game_state.tree();
+        self.game_state.phase() == GamePhase::Playing
+            && self.players[self.game_state.turn() as usize].is_bot()
+            && tree.children(tree.current()).is_empty()
+    }
+
+    // the tree search runs on a worker thread so the window keeps responding,
+    // its move is dropped if the position changed in the meantime
+    fn poll_bot_move(&mut self, player: Player) -> Option<Move> {
+        let position = (self.game_state.tree().current(), self.game_state.turn());
+        if let Some((searched, receiver)) = &self.pending_move {
+            if *searched == position {
+                match receiver.try_recv() {
+                    Ok(mv) => {
+                        self.pending_move = None;
+                        return Some(mv);
+                    }
+                    Err(mpsc::TryRecvError::Empty) => return None,
+                    Err(mpsc::TryRecvError::Disconnected) => {}
+                }
+            }
+            self.pending_move = None;
+        }
+        let Some(job) = player.search_job(
+            &self.game_state,
+            &self.search_params,
+            &mut self.bot_rng_state,
+        ) else {
+            return player.choose_move(
+                &self.game_state,
+                &self.search_params,
+                &mut self.bot_rng_state,
+            );
+        };
+        // no threads on the web
+        if cfg!(target_arch = "wasm32") {
+            return Some(job());
+        }
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(job());
+        });
+        self.pending_move = Some((position, receiver));
+        None
+    }
+
+    // one bot move per frame, and bots agree to whatever dead stones are marked
+    fn update_bots(&mut self) {
+        if self.bot_to_move() {
+            let player = self.players[self.game_state.turn() as usize];
+            if let Some(mv) = self.poll_bot_move(player) {
+                self.game_state.play_move(mv);
+            }
+        }
+        let unconfirmed = match &self.game_state.score {
+            Some(score) => [!score.black_confirmed, !score.white_confirmed],
+            None => [false; 2],
+        };
+        for player in [Turn::Black, Turn::White] {
+            if unconfirmed[player as usize]
+                && self.players[player as usize].is_bot()
+                && self.game_state.is_scoring()
+            {
+                self.game_state.confirm_score(player);
+            }
+        }
+    }
+
+    fn update(&mut self, queue: &wgpu::Queue) {
+        self.frame_count += 1;
+        const FPS_FAC: u64 = 10;
+        if self.frame_count % FPS_FAC == 0 {
+            self.fps_ring.push_back(
+                (FPS_FAC as f64) / (Instant::now() - self.last_frame_time).as_secs_f64(),
+            );
+
+            self.last_frame_time = Instant::now();
+        }
+
+        const SPEED: f64 = 0.1;
+        if self.input_state.back {
+            self.view_state.translate(SPEED, PI);
+        } else if self.input_state.forward {
+            self.view_state.translate(SPEED, 0.0);
+        }
+        if self.input_state.left {
+            self.view_state.translate(SPEED, PI / 2.0);
+        } else if self.input_state.right {
+            self.view_state.translate(SPEED, 3.0 * PI / 2.0);
+        }
+        const ANGULAR_SPEED: f64 = 0.05;
+        if self.input_state.cw {
+            self.view_state.rotate(ANGULAR_SPEED);
+        } else if self.input_state.ccw {
+            self.view_state.rotate(-ANGULAR_SPEED);
+        }
+
+        if let Some(pos) = self.drag_from {
+            if self.last_drag_pos != self.cursor_pos {
+                self.view_state.drag(pos, self.cursor_pos);
+                self.last_drag_pos = self.cursor_pos;
+            }
+        }
+
+        if self
+            .view_state
+            .camera
+            .distance(self.view_state.floating_origin)
+            > 2.0
+        {
+            self.view_state.update_floating_origin();
+            self.game_state
+                .update_floating_origin(&self.view_state.camera.reverse());
+        }
+
+        self.uniform.transform = self.view_state.get_camera_mat().into();
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform]),
+        );
+
+        self.game_state.update_clock(Instant::now());
+        self.update_bots();
+        // runs on the render thread, so it gets a smaller budget than the bots
+        if self.show_ownership {
+            let playouts = self.search_params.playouts.min(OWNERSHIP_PLAYOUTS);
+            self.game_state
+                .update_ownership(playouts, self.bot_rng_state);
+        } else if self.game_state.ownership.take().is_some() {
+            self.game_state.needs_render = true;
+        }
+
+        if self.game_state.needs_render {
+            self.link_instances = self.game_state.make_link_instances();
+            queue.write_buffer(
+                &self.link_instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.link_instances[..]),
+            );
+
+            self.stone_instances = self.game_state.make_stone_instances();
+            queue.write_buffer(
+                &self.stone_instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.stone_instances[..]),
+            );
+            self.game_state.needs_render = false;
+        }
+
+        // TODO don't need to be updating this every frame
+        self.outer_uniform.f = self.view_state.projection_factor as f32;
+        queue.write_buffer(
+            &self.outer_uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.outer_uniform]),
+        )
+    }
+
+    fn render_to_render_target(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render_target_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.render_target_tex_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.55,
+                        g: 0.4,
+                        b: 0.25,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.render_target_pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.link_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.link_instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.link_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(
+            0..self.models[1].indices.len() as _,
+            0,
+            0..self.link_instances.len() as _,
+        );
+
+        render_pass.set_vertex_buffer(0, self.stone_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.stone_instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.stone_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(
+            0..self.models[0].indices.len() as _,
+            0,
+            0..self.stone_instances.len() as _,
+        );
+    }
+
+    fn render_outer(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("outer_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.2,
+                        g: 0.2,
+                        b: 0.2,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.outer_render_pipeline);
+        render_pass.set_bind_group(0, &self.render_target_tex_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.outer_uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.render_target_vertex_buffer.slice(..));
+        render_pass.draw(0..RENDER_TARGET_VERTS.len() as _, 0..1);
+
+        self.text_render_state.render(&mut render_pass).unwrap();
+    }
+
+    // names next to the edge points, or every point once zoomed in far enough,
+    // shrinking along with the board toward the edge of the disk
+    fn point_labels(&self) -> Vec<PointLabel> {
+        let board = self.game_state.board();
+        let params = board.tiling_parameters();
+        let (width, height) = (self.config.width, self.config.height);
+        let projected: Vec<Option<(Vector2<f64>, f64)>> = (0..board.point_count() as i32)
+            .map(|i| {
+                self.view_state
+                    .world_to_pixel_coords(width, height, board.relative_transform(i))
+            })
+            .collect();
+
+        let mut labels = vec![];
+        for (i, &point) in projected.iter().enumerate() {
+            let Some((pixel, pixels_per_unit)) = point else {
+                continue;
+            };
+            if pixel.x < 0.0 || pixel.y < 0.0 || pixel.x > width as f64 || pixel.y > height as f64 {
+                continue;
+            }
+            let idx = i as i32;
+            let neighbors = board.neighbors(idx);
+            let is_edge = neighbors.len() < params.around_vertex as usize;
+            let spacing = params.distance * pixels_per_unit;
+            if spacing < ALL_LABEL_SPACING && !(is_edge && spacing >= EDGE_LABEL_SPACING) {
+                continue;
+            }
+
+            // edge labels go outward, away from the neighbors
+            let mut direction = vec2(1.0, 1.0).normalize();
+            if is_edge {
+                let shown: Vec<Vector2<f64>> = neighbors
+                    .iter()
+                    .filter_map(|&n| projected[n as usize].map(|(p, _)| p))
+                    .collect();
+                if !shown.is_empty() {
+                    let mean =
+                        shown.iter().fold(Vector2::zero(), |a, &p| a + p) / shown.len() as f64;
+                    let outward = pixel - mean;
+                    if outward.magnitude2() > 1e-6 {
+                        direction = outward.normalize();
+                    }
+                }
+            }
+
+            let scale = (spacing / LABEL_SPACING).min(MAX_LABEL_SCALE);
+            let size = self.text_render_state.label_size(idx) * scale;
+            let stone_radius = STONE_RADIUS * params.stone_scale * pixels_per_unit;
+            // far enough along the direction for the box to clear the stone
+            let reach =
+                stone_radius + 0.5 * (direction.x.abs() * size.x + direction.y.abs() * size.y);
+            let center = pixel + direction * reach;
+            labels.push(PointLabel {
+                idx,
+                left: (center.x - 0.5 * size.x) as f32,
+                top: (center.y - 0.5 * size.y) as f32,
+                scale: scale as f32,
+            });
+        }
+        labels
+    }
+
+    fn prepare_text(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<(), glyphon::PrepareError> {
+        let mut avg_fps = 0.0;
+        for &fps in self.fps_ring.iter() {
+            avg_fps += fps;
+        }
+        avg_fps /= self.fps_ring.len() as f64;
+
+        let camera_pos = self.view_state.camera.apply(SpinorT::Point::zero());
+        // let floating_origin_pos = self
+        //     .view_state
+        //     .floating_origin
+        //     .apply(SpinorT::Point::zero());
+
+        let hover_display = if let Some((pos, idx)) = self.hover_point_pos_idx {
+            format!(
+                "\nhovering over {:.1} ({:}, #{:})",
+                pos,
+                self.game_state.point_name(idx),
+                idx
+            )
+        } else {
+            "".into()
+        };
+
+        let left_text = format!(
+            "fps: {avg_fps:.2}\ncamera pos: {:.1}{:}",
+            camera_pos, hover_display
+        );
+
+        let score_display = if let Some(final_score) = &self.game_state.final_score {
+            format!(
+                "\nfinal score{:}{:}",
+                score_breakdown(final_score.rule, "black", &final_score.black),
+                score_breakdown(final_score.rule, "white", &final_score.white)
+            )
+        } else if let Some(score) = &self.game_state.score {
+            let confirm_display = match (score.black_confirmed, score.white_confirmed) {
+                (false, false) => "",
+                (true, false) => "\nblack confirmed",
+                (false, true) => "\nwhite confirmed",
+                (true, true) => "",
+            };
+            format!(
+                "\n{:?} scoring{:}{:}{:}\n{:?} confirms (enter)",
+                score.rule,
+                score_breakdown(score.rule, "black", &score.black),
+                score_breakdown(score.rule, "white", &score.white),
+                confirm_display,
+                score.next_to_confirm(self.game_state.turn())
+            )
+        } else {
+            "".into()
+        };
+
+        let (variation_idx, variation_count) = self.game_state.get_variation();
+        let variation_display = if variation_count > 1 {
+            format!("\nvariation {:}/{:}", variation_idx + 1, variation_count)
+        } else {
+            "".into()
+        };
+
+        let now = Instant::now();
+        let clock_display = if let Some(clock) = &self.game_state.clock {
+            format!(
+                "\nblack {:}\nwhite {:}",
+                clock.display(Turn::Black, now),
+                clock.display(Turn::White, now)
+            )
+        } else {
+            "".into()
+        };
+
+        let handicap_display = match self.game_state.handicap_to_place() {
+            0 => "".into(),
+            n => format!("\nplace {:} handicap stones", n),
+        };
+
+        let prisoners = (
+            self.game_state.prisoners(Turn::Black),
+            self.game_state.prisoners(Turn::White),
+        );
+        let prisoner_display = if prisoners != (0, 0) {
+            format!("\ncaptures: {:} / {:}", prisoners.0, prisoners.1)
+        } else {
+            "".into()
+        };
+
+        let estimate_display = match &self.game_state.ownership {
+            Some(ownership) if ownership.score > 0.0 => {
+                format!("\nestimate: B+{:.1}", ownership.score)
+            }
+            Some(ownership) => format!("\nestimate: W+{:.1}", -ownership.score),
+            None => "".into(),
+        };
+
+        let illegal_display = match self.game_state.illegal_move {
+            Some(reason) => format!("\nillegal move: {:}", reason),
+            None => "".into(),
+        };
+
+        let result_display = if let Some(result) = &self.game_state.result {
+            format!("\nresult: {:}", result)
+        } else {
+            "".into()
+        };
+
+        let right_text = format!(
+            "turn {:}{:}{:}{:}{:}{:}{:}{:}{:}",
+            self.game_state.get_turn_count(),
+            clock_display,
+            handicap_display,
+            variation_display,
+            prisoner_display,
+            score_display,
+            estimate_display,
+            illegal_display,
+            result_display
+        );
+
+        let labels = if self.show_labels {
+            let board = self.game_state.board();
+            let names: Vec<PointName> = (0..board.point_count() as i32)
+                .map(|i| board.point_name(i))
+                .collect();
+            self.text_render_state.set_labels(&names);
+            self.point_labels()
+        } else {
+            vec![]
+        };
+
+        self.text_render_state.prepare(
+            &left_text,
+            &right_text,
+            device,
+            queue,
+            &self.config,
+            &labels,
+        )
+    }
+
+    fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface: &wgpu::Surface,
+    ) -> Result<(), wgpu::SurfaceError> {
+        self.prepare_text(device, queue).unwrap();
+
+        let output = surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        // TODO can/should this be reused?
+        let mut render_target_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("render_target_encoder"),
+            });
+        self.render_to_render_target(&mut render_target_encoder);
+        let mut commands = vec![render_target_encoder.finish()];
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("encoder"),
+        });
+        self.render_outer(&mut encoder, &view);
+        commands.push(encoder.finish());
+
+        queue.submit(commands);
+        output.present();
+
+        self.text_render_state.post_render();
+
+        Ok(())
+    }
+}
+
+// State is specialized on the geometry, so the one in use is picked at runtime here
+enum GeometryState {
+    Euclidian(Box<State<SpinorEuclidian>>),
+    Hyperbolic(Box<State<SpinorHyperbolic>>),
+    Spherical(Box<State<SpinorSpherical>>),
+}
+
+impl GeometryState {
+    fn new(
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_caps: &wgpu::SurfaceCapabilities,
+        geometry: Geometry,
+        (edge_count, sides, around_vertex): (u32, u32, u32),
+    ) -> Self {
+        match geometry {
+            Geometry::Euclidian => GeometryState::Euclidian(Box::new(State::new(
+                window,
+                device,
+                queue,
+                surface_caps,
+                TilingParameters::new::<SpinorEuclidian>(edge_count, sides, around_vertex),
+            ))),
+            Geometry::Hyperbolic => GeometryState::Hyperbolic(Box::new(State::new(
+                window,
+                device,
+                queue,
+                surface_caps,
+                TilingParameters::new::<SpinorHyperbolic>(edge_count, sides, around_vertex),
+            ))),
+            Geometry::Spherical => GeometryState::Spherical(Box::new(State::new(
+                window,
+                device,
+                queue,
+                surface_caps,
+                TilingParameters::new::<SpinorSpherical>(edge_count, sides, around_vertex),
+            ))),
+        }
+    }
+
+    fn geometry(&self) -> Geometry {
+        match self {
+            GeometryState::Euclidian(_) => Geometry::Euclidian,
+            GeometryState::Hyperbolic(_) => Geometry::Hyperbolic,
+            GeometryState::Spherical(_) => Geometry::Spherical,
+        }
+    }
+}
+
+// runs the same code against whichever State is active
+macro_rules! with_state {
+    ($geometry_state:expr, $state:ident => $body:expr) => {
+        match $geometry_state {
+            GeometryState::Euclidian($state) => $body,
+            GeometryState::Hyperbolic($state) => $body,
+            GeometryState::Spherical($state) => $body,
+        }
+    };
+}
+
+struct PersistentState<'a> {
+    window: &'a Window,
+    surface: wgpu::Surface<'a>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface_caps: wgpu::SurfaceCapabilities,
+
+    state: GeometryState,
+}
+
+impl<'a> PersistentState<'a> {
+    async fn new(
+        window: &'a Window,
+        geometry: Geometry,
+        tiling: (u32, u32, u32),
+    ) -> PersistentState<'a> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            #[cfg(not(target_arch = "wasm32"))]
+            backends: wgpu::Backends::PRIMARY,
+            #[cfg(target_arch = "wasm32")]
+            backends: wgpu::Backends::GL,
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(window).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    // TODO presumably this can be made optional?
+                    //required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+                    required_features: wgpu::Features::default(),
+                    required_limits: if cfg!(target_arch = "wasm32") {
+                        wgpu::Limits::downlevel_webgl2_defaults()
+                    } else {
+                        wgpu::Limits::default()
+                    },
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let state = GeometryState::new(window, &device, &queue, &surface_caps, geometry, tiling);
+
+        Self {
+            window,
+            surface,
+            device,
+            queue,
+            surface_caps,
+            state,
+        }
+    }
+
+    fn reset_state(&mut self, geometry: Geometry, tiling: (u32, u32, u32)) {
+        self.state = GeometryState::new(
+            self.window,
+            &self.device,
+            &self.queue,
+            &self.surface_caps,
+            geometry,
+            tiling,
+        );
+    }
+
+    fn load_state<SpinorT: Spinor>(&self, game_state: GameState<SpinorT>) -> Box<State<SpinorT>> {
+        let mut state = Box::new(State::new(
+            self.window,
+            &self.device,
+            &self.queue,
+            &self.surface_caps,
+            game_state.tiling_parameters(),
+        ));
+        state.game_state = game_state;
+        state
+    }
+
+    fn load_game(&mut self, sgf: &str) -> Result<(), SgfError> {
+        self.state = match sgf_geometry(sgf)? {
+            Geometry::Euclidian => {
+                GeometryState::Euclidian(self.load_state(GameState::from_sgf(sgf)?))
+            }
+            Geometry::Hyperbolic => {
+                GeometryState::Hyperbolic(self.load_state(GameState::from_sgf(sgf)?))
+            }
+            Geometry::Spherical => {
+                GeometryState::Spherical(self.load_state(GameState::from_sgf(sgf)?))
+            }
+        };
+        Ok(())
+    }
+
+    fn save_game(&self) -> String {
+        with_state!(&self.state, state => state.game_state.to_sgf())
+    }
+
+    fn resize(&mut self, new_size: Option<winit::dpi::PhysicalSize<u32>>) {
+        with_state!(&mut self.state, state => state.resize(
+            &self.device,
+            &self.queue,
+            &self.surface,
+            new_size.unwrap_or(state.size),
+        ))
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        with_state!(&mut self.state, state => state.input(event))
+    }
+    fn update(&mut self) {
+        with_state!(&mut self.state, state => state.update(&self.queue))
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        with_state!(&mut self.state, state => state.render(&self.device, &self.queue, &self.surface))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CustomEvent {
+    size: LogicalSize<u32>,
+}
+
+// board presets for the number keys, applied on escape
+fn preset_tiling(geometry: Geometry, key: KeyCode) -> (u32, u32, u32) {
+    match geometry {
+        Geometry::Euclidian => match key {
+            KeyCode::Digit2 => (9, 4, 4),
+            KeyCode::Digit3 => (13, 4, 4),
+            _ => geometry.default_tiling(),
+        },
+        Geometry::Hyperbolic => match key {
+            KeyCode::Digit2 => (7, 5, 4),
+            KeyCode::Digit3 => (9, 5, 4),
+            KeyCode::Digit4 => (7, 5, 5),
+            KeyCode::Digit5 => (7, 5, 7),
+            KeyCode::Digit6 => (7, 6, 4),
+            KeyCode::Digit7 => (7, 7, 4),
+            KeyCode::Digit8 => (7, 8, 4),
+            KeyCode::Digit9 => (7, 9, 4),
+            _ => geometry.default_tiling(),
+        },
+        Geometry::Spherical => match key {
+            KeyCode::Digit2 => (7, 3, 5),
+            KeyCode::Digit3 => (7, 4, 3),
+            KeyCode::Digit4 => (7, 3, 4),
+            KeyCode::Digit5 => (3, 3, 3),
+            _ => geometry.default_tiling(),
+        },
+    }
+}
+
+pub async fn run_app(geometry: Geometry) {
+    let event_loop = EventLoop::<CustomEvent>::with_user_event().build().unwrap();
+    let window = event_loop
+        .create_window(
+            WindowAttributes::default()
+                .with_inner_size(LogicalSize {
+                    width: 1024,
+                    height: 1024,
+                })
+                .with_title("hypergo"),
+        )
+        .unwrap();
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::closure::Closure;
+        use winit::platform::web::WindowExtWebSys;
+
+        let web_window = web_sys::window().unwrap();
+        let dst = web_window
+            .document()
+            .unwrap()
+            .get_element_by_id("game-render")
+            .unwrap();
+        let canvas = web_sys::Element::from(window.canvas().unwrap());
+        dst.append_child(&canvas).ok().unwrap();
+
+        const SIZE_HACK: u32 = 3;
+        fn get_logical_size(web_window: &web_sys::Window) -> LogicalSize<u32> {
+            let width = web_window.inner_width().unwrap().as_f64().unwrap() as u32;
+            let height = web_window.inner_height().unwrap().as_f64().unwrap() as u32;
+            LogicalSize::new(width - SIZE_HACK, height - SIZE_HACK)
+        }
+
+        let _ = window.request_inner_size(get_logical_size(&web_window));
+
+        let event_loop_proxy = event_loop.create_proxy();
+        let closure = Closure::wrap(Box::new(move |_web_event: web_sys::Event| {
+            let web_window = web_sys::window().unwrap();
+            event_loop_proxy
+                .send_event(CustomEvent {
+                    size: get_logical_size(&web_window),
+                })
+                .unwrap();
+        }) as Box<dyn FnMut(_)>);
+        web_window
+            .add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())
+            .unwrap();
+        closure.forget();
+    }
+
+    // TODO hack
+    let mut last_num_pressed = KeyCode::Digit1;
+
+    let mut state = PersistentState::new(&window, geometry, geometry.default_tiling()).await;
+    let mut surface_configured = false;
+
+    // TODO how is the non-deprecated version of this event loop supposed to work?
+    event_loop
+        .run(move |event, control_flow| match event {
+            /*             Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } => state.handle_mouse(x, y), */
+            Event::UserEvent(CustomEvent { size }) => {
+                let _ = state.window.request_inner_size(size);
+                info!("web resize event {:?}", size);
+            }
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == state.window.id() => {
+                if !state.input(event) {
+                    match event {
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(keycode),
+                                    ..
+                                },
+                            ..
+                        } => match keycode {
+                            KeyCode::Escape => {
+                                info!("resetting state");
+                                let geometry = state.state.geometry();
+                                state.reset_state(
+                                    geometry,
+                                    preset_tiling(geometry, last_num_pressed),
+                                );
+                                state.resize(None)
+                            }
+                            KeyCode::KeyG => {
+                                let geometry = state.state.geometry().next();
+                                info!("switching to {:?} geometry", geometry);
+                                state.reset_state(geometry, geometry.default_tiling());
+                                state.resize(None)
+                            }
+                            #[cfg(not(target_arch = "wasm32"))]
+                            KeyCode::F5 => match std::fs::write(SAVE_PATH, state.save_game()) {
+                                Ok(_) => info!("saved game to {}", SAVE_PATH),
+                                Err(e) => log::error!("failed to save game: {}", e),
+                            },
+                            #[cfg(not(target_arch = "wasm32"))]
+                            KeyCode::F9 => match std::fs::read_to_string(SAVE_PATH) {
+                                Ok(sgf) => match state.load_game(&sgf) {
+                                    Ok(_) => {
+                                        info!("loaded game from {}", SAVE_PATH);
+                                        state.resize(None)
+                                    }
+                                    Err(e) => log::error!("failed to parse {}: {}", SAVE_PATH, e),
+                                },
+                                Err(e) => log::error!("failed to load game: {}", e),
+                            },
+                            KeyCode::Digit0
+                            | KeyCode::Digit1
+                            | KeyCode::Digit2
+                            | KeyCode::Digit3
+                            | KeyCode::Digit4
+                            | KeyCode::Digit5
+                            | KeyCode::Digit6
+                            | KeyCode::Digit7
+                            | KeyCode::Digit8
+                            | KeyCode::Digit9 => {
+                                last_num_pressed = *keycode;
+                            }
+                            _ => {}
+                        },
+                        WindowEvent::CloseRequested => control_flow.exit(),
+                        WindowEvent::Resized(size) => {
+                            surface_configured = true;
+                            state.resize(Some(*size));
+                            info!("resize event {:?}", *size);
+                        }
+                        WindowEvent::RedrawRequested => {
+                            state.window.request_redraw();
+
+                            if !surface_configured {
+                                return;
+                            }
+
+                            state.update();
+                            match state.render() {
+                                Ok(_) => {}
+                                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                                    state.resize(None)
+                                }
+                                Err(wgpu::SurfaceError::OutOfMemory) => {
+                                    log::error!("OutOfMemory");
+                                    control_flow.exit();
+                                }
+                                Err(wgpu::SurfaceError::Timeout) => {
+                                    log::warn!("Surface timeout")
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        })
+        .unwrap();
+}
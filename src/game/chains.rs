@@ -0,0 +1,263 @@
+use super::*;
+
+// Stones of a chain form a circular list through next_stone, and every stone
+// points at the chain head. Size and pseudo liberties (every stone, empty
+// neighbor pair, so duplicates are counted) are only kept on the head.
+// A chain has no liberties exactly when its pseudo liberties are zero.
+impl<SpinorT: Spinor> Board<SpinorT> {
+    pub fn chain_head(&self, idx: i32) -> i32 {
+        self.points[idx as usize].chain
+    }
+
+    pub fn chain_size(&self, idx: i32) -> u32 {
+        match self.chain_head(idx) {
+            -1 => 0,
+            head => self.points[head as usize].chain_size,
+        }
+    }
+
+    pub fn chain_stones(&self, idx: i32) -> Vec<i32> {
+        let mut stones = vec![];
+        if self.chain_head(idx) < 0 {
+            return stones;
+        }
+        let mut i = idx;
+        loop {
+            stones.push(i);
+            i = self.points[i as usize].next_stone;
+            if i == idx {
+                return stones;
+            }
+        }
+    }
+
+    pub fn has_liberties(&self, idx: i32) -> bool {
+        match self.chain_head(idx) {
+            -1 => true,
+            head => self.points[head as usize].pseudo_liberties > 0,
+        }
+    }
+
+    // whether a stone on point would take the last liberty of the chain through idx
+    pub fn is_last_liberty(&self, idx: i32, point: i32) -> bool {
+        let mut i = idx;
+        loop {
+            for &n in &self.points[i as usize].neighbors {
+                if n != point && self.points[n as usize].ty == StoneType::Empty {
+                    return false;
+                }
+            }
+            i = self.points[i as usize].next_stone;
+            if i == idx {
+                return true;
+            }
+        }
+    }
+
+    pub fn liberties(&self, idx: i32) -> Vec<i32> {
+        let mut liberties: Vec<i32> = self
+            .chain_stones(idx)
+            .iter()
+            .flat_map(|&i| self.points[i as usize].neighbors.iter())
+            .copied()
+            .filter(|&n| self.points[n as usize].ty == StoneType::Empty)
+            .collect();
+        liberties.sort_unstable();
+        liberties.dedup();
+        liberties
+    }
+
+    // adds a stone on an empty point, merging it with any friendly chains
+    pub(super) fn place_stone(&mut self, idx: i32, ty: StoneType) {
+        debug_assert_eq!(self.points[idx as usize].ty, StoneType::Empty);
+        self.set_stone(idx, ty);
+
+        let mut pseudo_liberties = 0;
+        for k in 0..self.points[idx as usize].neighbors.len() {
+            let n = self.points[idx as usize].neighbors[k];
+            match self.chain_head(n) {
+                -1 => pseudo_liberties += 1,
+                head => self.points[head as usize].pseudo_liberties -= 1,
+            }
+        }
+        let point = &mut self.points[idx as usize];
+        point.chain = idx;
+        point.next_stone = idx;
+        point.chain_size = 1;
+        point.pseudo_liberties = pseudo_liberties;
+
+        for k in 0..self.points[idx as usize].neighbors.len() {
+            let n = self.points[idx as usize].neighbors[k];
+            if self.points[n as usize].ty == ty {
+                self.merge_chains(idx, n);
+            }
+        }
+    }
+
+    // removes the stone on idx, splitting its chain if needed
+    pub(super) fn remove_stone(&mut self, idx: i32) {
+        let ty = self.points[idx as usize].ty;
+        debug_assert_ne!(ty, StoneType::Empty);
+        for i in self.chain_stones(idx) {
+            self.points[i as usize].chain = -1;
+        }
+        self.set_stone(idx, StoneType::Empty);
+
+        for k in 0..self.points[idx as usize].neighbors.len() {
+            let n = self.points[idx as usize].neighbors[k];
+            let head = self.chain_head(n);
+            if head >= 0 {
+                self.points[head as usize].pseudo_liberties += 1;
+            }
+        }
+        // what is left of the old chain gets rebuilt from scratch
+        for k in 0..self.points[idx as usize].neighbors.len() {
+            let n = self.points[idx as usize].neighbors[k];
+            if self.points[n as usize].ty == ty && self.chain_head(n) < 0 {
+                self.rebuild_chain(n);
+            }
+        }
+    }
+
+    // removes every stone in the chain of idx, returning them
+    pub(super) fn remove_chain(&mut self, idx: i32) -> Vec<i32> {
+        let stones = self.chain_stones(idx);
+        for &i in &stones {
+            self.points[i as usize].chain = -1;
+            self.set_stone(i, StoneType::Empty);
+        }
+        for &i in &stones {
+            for k in 0..self.points[i as usize].neighbors.len() {
+                let head = self.chain_head(self.points[i as usize].neighbors[k]);
+                if head >= 0 {
+                    self.points[head as usize].pseudo_liberties += 1;
+                }
+            }
+        }
+        stones
+    }
+
+    fn merge_chains(&mut self, a: i32, b: i32) {
+        let (mut head_a, mut head_b) = (self.chain_head(a), self.chain_head(b));
+        if head_a == head_b {
+            return;
+        }
+        // relabel the smaller chain
+        if self.points[head_a as usize].chain_size < self.points[head_b as usize].chain_size {
+            std::mem::swap(&mut head_a, &mut head_b);
+        }
+        for i in self.chain_stones(head_b) {
+            self.points[i as usize].chain = head_a;
+        }
+        let next_a = self.points[head_a as usize].next_stone;
+        let next_b = self.points[head_b as usize].next_stone;
+        self.points[head_a as usize].next_stone = next_b;
+        self.points[head_b as usize].next_stone = next_a;
+
+        let (size, pseudo_liberties) = {
+            let b = &self.points[head_b as usize];
+            (b.chain_size, b.pseudo_liberties)
+        };
+        let a = &mut self.points[head_a as usize];
+        a.chain_size += size;
+        a.pseudo_liberties += pseudo_liberties;
+    }
+
+    // flood fills the chain through idx, for stones not in any chain
+    fn rebuild_chain(&mut self, idx: i32) {
+        let ty = self.points[idx as usize].ty;
+        let mut stones = vec![idx];
+        let mut search_stack = vec![idx];
+        self.points[idx as usize].chain = idx;
+        while let Some(i) = search_stack.pop() {
+            for k in 0..self.points[i as usize].neighbors.len() {
+                let n = self.points[i as usize].neighbors[k];
+                let point = &mut self.points[n as usize];
+                if point.ty == ty && point.chain < 0 {
+                    point.chain = idx;
+                    stones.push(n);
+                    search_stack.push(n);
+                }
+            }
+        }
+
+        let mut pseudo_liberties = 0;
+        for (k, &i) in stones.iter().enumerate() {
+            let point = &self.points[i as usize];
+            pseudo_liberties += point
+                .neighbors
+                .iter()
+                .filter(|&&n| self.points[n as usize].ty == StoneType::Empty)
+                .count() as u32;
+            self.points[i as usize].next_stone = stones[(k + 1) % stones.len()];
+        }
+        let head = &mut self.points[idx as usize];
+        head.chain_size = stones.len() as u32;
+        head.pseudo_liberties = pseudo_liberties;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::zobrist::next_key;
+    use crate::geometry::euclidian::SpinorEuclidian;
+
+    // compares against a plain flood fill of every chain
+    fn check_chains(board: &Board<SpinorEuclidian>) {
+        for idx in 0..board.point_count() as i32 {
+            let ty = board.stone(idx);
+            if ty == StoneType::Empty {
+                assert_eq!(board.chain_head(idx), -1);
+                continue;
+            }
+            let mut stones = vec![idx];
+            let mut search_stack = vec![idx];
+            while let Some(i) = search_stack.pop() {
+                for &n in board.neighbors(i) {
+                    if board.stone(n) == ty && !stones.contains(&n) {
+                        stones.push(n);
+                        search_stack.push(n);
+                    }
+                }
+            }
+            let mut liberties: Vec<i32> = stones
+                .iter()
+                .flat_map(|&i| board.neighbors(i).iter().copied())
+                .filter(|&n| board.stone(n) == StoneType::Empty)
+                .collect();
+            liberties.sort_unstable();
+            liberties.dedup();
+
+            let mut chain = board.chain_stones(idx);
+            chain.sort_unstable();
+            stones.sort_unstable();
+            assert_eq!(chain, stones);
+            assert_eq!(board.chain_size(idx), stones.len() as u32);
+            assert_eq!(board.liberties(idx), liberties);
+            assert_eq!(board.has_liberties(idx), !liberties.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_chains_through_captures_and_undo() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        let mut state = 1;
+        for _ in 0..300 {
+            let idx = (next_key(&mut state) % game.point_count() as u64) as i32;
+            game.play_point(idx);
+            check_chains(game.board());
+        }
+        let moves = game.get_turn_count() - 1;
+        for _ in 0..moves {
+            game.go_to_parent();
+            check_chains(game.board());
+        }
+        assert!((0..25).all(|i| game.board().stone(i) == StoneType::Empty));
+        for _ in 0..moves {
+            game.go_to_child();
+            check_chains(game.board());
+        }
+    }
+}
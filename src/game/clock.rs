@@ -0,0 +1,373 @@
+use web_time::{Duration, Instant};
+
+use super::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeControl {
+    Absolute {
+        main: Duration,
+    },
+    // periods are used up one at a time, a move inside a period resets it
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: u32,
+    },
+    // each period has to fit this many stones
+    Canadian {
+        main: Duration,
+        period: Duration,
+        stones: u32,
+    },
+    // added back after every move
+    Fischer {
+        main: Duration,
+        increment: Duration,
+    },
+}
+
+impl TimeControl {
+    fn main(&self) -> Duration {
+        match *self {
+            TimeControl::Absolute { main }
+            | TimeControl::ByoYomi { main, .. }
+            | TimeControl::Canadian { main, .. }
+            | TimeControl::Fischer { main, .. } => main,
+        }
+    }
+}
+
+// in whole seconds, "absolute:600", "byoyomi:600:30:5", "canadian:600:300:25",
+// "fischer:300:10"
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TimeControl::Absolute { main } => write!(f, "absolute:{}", main.as_secs()),
+            TimeControl::ByoYomi {
+                main,
+                period,
+                periods,
+            } => write!(
+                f,
+                "byoyomi:{}:{}:{}",
+                main.as_secs(),
+                period.as_secs(),
+                periods
+            ),
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } => write!(
+                f,
+                "canadian:{}:{}:{}",
+                main.as_secs(),
+                period.as_secs(),
+                stones
+            ),
+            TimeControl::Fischer { main, increment } => {
+                write!(f, "fischer:{}:{}", main.as_secs(), increment.as_secs())
+            }
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let kind = parts.next().unwrap_or("");
+        let values = parts
+            .map(|v| v.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("bad time control {:?}", s))?;
+        let secs = Duration::from_secs;
+        match (kind, values.as_slice()) {
+            ("absolute", &[main]) => Ok(TimeControl::Absolute { main: secs(main) }),
+            ("byoyomi", &[main, period, periods]) if period > 0 && periods > 0 => {
+                Ok(TimeControl::ByoYomi {
+                    main: secs(main),
+                    period: secs(period),
+                    periods: periods as u32,
+                })
+            }
+            ("canadian", &[main, period, stones]) if period > 0 && stones > 0 => {
+                Ok(TimeControl::Canadian {
+                    main: secs(main),
+                    period: secs(period),
+                    stones: stones as u32,
+                })
+            }
+            ("fischer", &[main, increment]) => Ok(TimeControl::Fischer {
+                main: secs(main),
+                increment: secs(increment),
+            }),
+            _ => Err(format!("bad time control {:?}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct PlayerClock {
+    // main time, or what is left of the current period in overtime
+    remaining: Duration,
+    overtime: bool,
+    periods_left: u32,
+    stones_left: u32,
+}
+
+pub struct GameClock {
+    control: TimeControl,
+    // indexed by Turn
+    clocks: [PlayerClock; 2],
+    // whose clock is running and since when
+    running: Option<(Turn, Instant)>,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> Self {
+        let clock = PlayerClock {
+            remaining: control.main(),
+            overtime: false,
+            periods_left: 0,
+            stones_left: 0,
+        };
+        Self {
+            control,
+            clocks: [clock; 2],
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    pub fn running(&self) -> Option<Turn> {
+        self.running.map(|(player, _)| player)
+    }
+
+    // None once the player runs out
+    fn spend(&self, clock: PlayerClock, mut elapsed: Duration) -> Option<PlayerClock> {
+        let mut clock = clock;
+        if !clock.overtime {
+            if elapsed < clock.remaining {
+                clock.remaining -= elapsed;
+                return Some(clock);
+            }
+            elapsed -= clock.remaining;
+            clock.overtime = true;
+            match self.control {
+                TimeControl::ByoYomi {
+                    period, periods, ..
+                } => {
+                    clock.remaining = period;
+                    clock.periods_left = periods;
+                }
+                TimeControl::Canadian { period, stones, .. } => {
+                    clock.remaining = period;
+                    clock.stones_left = stones;
+                }
+                TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => return None,
+            }
+        }
+        if let TimeControl::ByoYomi { period, .. } = self.control {
+            while elapsed >= clock.remaining {
+                elapsed -= clock.remaining;
+                clock.periods_left -= 1;
+                if clock.periods_left == 0 {
+                    return None;
+                }
+                clock.remaining = period;
+            }
+        }
+        if elapsed >= clock.remaining {
+            return None;
+        }
+        clock.remaining -= elapsed;
+        Some(clock)
+    }
+
+    fn finish_move(&self, clock: PlayerClock) -> PlayerClock {
+        let mut clock = clock;
+        match self.control {
+            TimeControl::Absolute { .. } => {}
+            TimeControl::ByoYomi { period, .. } => {
+                if clock.overtime {
+                    clock.remaining = period;
+                }
+            }
+            TimeControl::Canadian { period, stones, .. } => {
+                if clock.overtime {
+                    clock.stones_left -= 1;
+                    if clock.stones_left == 0 {
+                        clock.remaining = period;
+                        clock.stones_left = stones;
+                    }
+                }
+            }
+            TimeControl::Fischer { increment, .. } => clock.remaining += increment,
+        }
+        clock
+    }
+
+    // stops the running clock, returning the player if they ran out of time
+    pub fn pause(&mut self, now: Instant) -> Option<Turn> {
+        let (player, since) = self.running.take()?;
+        match self.spend(self.clocks[player as usize], now - since) {
+            Some(clock) => {
+                self.clocks[player as usize] = clock;
+                None
+            }
+            None => {
+                self.clocks[player as usize].remaining = Duration::ZERO;
+                Some(player)
+            }
+        }
+    }
+
+    pub fn start(&mut self, player: Turn, now: Instant) {
+        if self.running().is_some_and(|running| running != player) {
+            self.pause(now);
+        }
+        if self.running.is_none() {
+            self.running = Some((player, now));
+        }
+    }
+
+    // the running player made a move, hands over to the other one
+    pub fn press(&mut self, now: Instant) -> Option<Turn> {
+        let player = self.running()?;
+        if let Some(flagged) = self.pause(now) {
+            return Some(flagged);
+        }
+        self.clocks[player as usize] = self.finish_move(self.clocks[player as usize]);
+        self.start(player.other(), now);
+        None
+    }
+
+    pub fn flagged(&self, now: Instant) -> Option<Turn> {
+        let (player, since) = self.running?;
+        self.spend(self.clocks[player as usize], now - since)
+            .is_none()
+            .then_some(player)
+    }
+
+    // "4:59", with the periods or stones left in overtime
+    pub fn display(&self, player: Turn, now: Instant) -> String {
+        let mut clock = self.clocks[player as usize];
+        if let Some((running, since)) = self.running {
+            if running == player {
+                clock = self.spend(clock, now - since).unwrap_or(PlayerClock {
+                    remaining: Duration::ZERO,
+                    ..clock
+                });
+            }
+        }
+        let secs = clock.remaining.as_secs();
+        let time = format!("{}:{:02}", secs / 60, secs % 60);
+        match self.control {
+            TimeControl::ByoYomi { .. } if clock.overtime => {
+                format!("{} ({} left)", time, clock.periods_left)
+            }
+            TimeControl::Canadian { .. } if clock.overtime => {
+                format!("{} / {} stones", time, clock.stones_left)
+            }
+            _ => time,
+        }
+    }
+}
+
+impl<SpinorT: Spinor> GameState<SpinorT> {
+    pub fn set_time_control(&mut self, control: TimeControl) {
+        self.clock = Some(GameClock::new(control));
+    }
+
+    // runs the clock of the player to move while the game is being played, a loss
+    // on time finishes the game
+    pub fn update_clock(&mut self, now: Instant) {
+        let playing = self.phase() == GamePhase::Playing && self.handicap_to_place == 0;
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        let flagged = if playing {
+            clock.start(self.turn, now);
+            clock.flagged(now)
+        } else {
+            clock.pause(now)
+        };
+        if let Some(player) = flagged {
+            clock.pause(now);
+            self.finish(GameResult {
+                winner: Some(player.other()),
+                reason: ResultReason::Timeout,
+            });
+        }
+    }
+
+    // after each move or pass
+    pub(super) fn press_clock(&mut self) {
+        let now = Instant::now();
+        if let Some(clock) = &mut self.clock {
+            if let Some(player) = clock.press(now) {
+                self.finish(GameResult {
+                    winner: Some(player.other()),
+                    reason: ResultReason::Timeout,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_moves(clock: &mut GameClock, start: Instant, move_secs: &[u64]) -> Option<Turn> {
+        let mut now = start;
+        clock.start(Turn::Black, now);
+        for &secs in move_secs {
+            now += Duration::from_secs(secs);
+            if let Some(flagged) = clock.press(now) {
+                return Some(flagged);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_time_controls() {
+        let start = Instant::now();
+        let control: TimeControl = "byoyomi:10:5:2".parse().unwrap();
+        assert_eq!(control.to_string(), "byoyomi:10:5:2");
+        let mut clock = GameClock::new(control);
+        // black goes into overtime and uses up one period, white plays fast
+        assert_eq!(play_moves(&mut clock, start, &[12, 1, 4, 1, 7, 1]), None);
+        assert_eq!(clock.display(Turn::Black, start), "0:05 (1 left)");
+        let mut clock = GameClock::new(control);
+        assert_eq!(
+            play_moves(&mut clock, start, &[12, 1, 11]),
+            Some(Turn::Black)
+        );
+
+        let mut clock = GameClock::new("canadian:0:10:2".parse().unwrap());
+        assert_eq!(play_moves(&mut clock, start, &[4, 1, 4, 1, 9]), None);
+        assert_eq!(clock.display(Turn::Black, start), "0:01 / 1 stones");
+        let mut clock = GameClock::new("canadian:0:10:2".parse().unwrap());
+        assert_eq!(play_moves(&mut clock, start, &[6, 1, 5]), Some(Turn::Black));
+
+        let mut clock = GameClock::new("fischer:10:5".parse().unwrap());
+        assert_eq!(play_moves(&mut clock, start, &[8, 1, 5, 1, 5]), None);
+        assert_eq!(clock.display(Turn::Black, start), "0:07");
+        assert_eq!(clock.display(Turn::White, start), "0:18");
+
+        let mut clock = GameClock::new("absolute:10".parse().unwrap());
+        assert_eq!(play_moves(&mut clock, start, &[8, 1]), None);
+        assert_eq!(
+            clock.flagged(start + Duration::from_secs(11)),
+            Some(Turn::Black)
+        );
+        assert!("fischer:10".parse::<TimeControl>().is_err());
+    }
+}
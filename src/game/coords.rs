@@ -0,0 +1,176 @@
+use std::f64::consts::TAU;
+
+use super::*;
+
+// angles and distances get rounded to this many steps per unit before sorting, so
+// floating point noise can't swap points around
+const SORT_STEPS: f64 = 1e9;
+
+// stable name for a point, the ring counted in links out from the center points
+// and the position counterclockwise around that ring from the positive x axis,
+// both starting at 1 and written "3-17"
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct PointName {
+    pub ring: u32,
+    pub index: u32,
+}
+
+impl fmt::Display for PointName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ring, self.index)
+    }
+}
+
+impl FromStr for PointName {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ring, index) = s.trim().split_once('-').ok_or(())?;
+        let ring = ring.parse::<u32>().map_err(|_| ())?;
+        let index = index.parse::<u32>().map_err(|_| ())?;
+        if ring == 0 || index == 0 {
+            return Err(());
+        }
+        Ok(PointName { ring, index })
+    }
+}
+
+impl<SpinorT: Spinor> Board<SpinorT> {
+    // names only depend on the links and on where the points are, not on the order
+    // make_board happened to add them in
+    pub(super) fn assign_names(&mut self) {
+        let origin = SpinorT::Point::zero();
+        let distances: Vec<f64> = self.points.iter().map(|p| p.pos.distance(origin)).collect();
+        let nearest = distances.iter().copied().fold(f64::INFINITY, f64::min);
+
+        let mut rings = vec![u32::MAX; self.points.len()];
+        let mut queue = VecDeque::new();
+        for (i, &d) in distances.iter().enumerate() {
+            if d <= nearest + 1e-6 {
+                rings[i] = 1;
+                queue.push_back(i);
+            }
+        }
+        while let Some(i) = queue.pop_front() {
+            for &n in &self.points[i].neighbors {
+                if rings[n as usize] == u32::MAX {
+                    rings[n as usize] = rings[i] + 1;
+                    queue.push_back(n as usize);
+                }
+            }
+        }
+        // points the boundary cut off from the center go in one more ring outside
+        let outside = rings.iter().filter(|&&r| r != u32::MAX).max().unwrap_or(&0) + 1;
+        for ring in rings.iter_mut().filter(|r| **r == u32::MAX) {
+            *ring = outside;
+        }
+
+        let full_turn = (TAU * SORT_STEPS).round() as i64;
+        let sort_key = |i: usize| {
+            let angle = self.points[i].pos.angle().rem_euclid(TAU);
+            (
+                rings[i],
+                (angle * SORT_STEPS).round() as i64 % full_turn,
+                (distances[i] * SORT_STEPS).round() as i64,
+            )
+        };
+        let mut order: Vec<usize> = (0..self.points.len()).collect();
+        order.sort_by_key(|&i| sort_key(i));
+
+        self.names = vec![PointName { ring: 0, index: 0 }; self.points.len()];
+        let mut index = 0;
+        for (k, &i) in order.iter().enumerate() {
+            index = if k > 0 && rings[order[k - 1]] == rings[i] {
+                index + 1
+            } else {
+                1
+            };
+            self.names[i] = PointName {
+                ring: rings[i],
+                index,
+            };
+        }
+    }
+
+    pub fn point_name(&self, idx: i32) -> PointName {
+        self.names[idx as usize]
+    }
+
+    pub fn point_by_name(&self, name: PointName) -> Option<i32> {
+        self.names.iter().position(|&n| n == name).map(|i| i as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+    use crate::geometry::spherical::SpinorSpherical;
+
+    fn check_names<SpinorT: Spinor>(board: &Board<SpinorT>) {
+        let mut names: Vec<PointName> = (0..board.point_count() as i32)
+            .map(|i| board.point_name(i))
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(board.point_by_name(*name), Some(i as i32));
+            assert_eq!(name.to_string().parse(), Ok(*name));
+        }
+        // every ring numbered 1.. without gaps
+        names.sort();
+        for pair in names.windows(2) {
+            let [a, b] = pair else { unreachable!() };
+            if a.ring == b.ring {
+                assert_eq!(b.index, a.index + 1);
+            } else {
+                assert_eq!((b.ring, b.index), (a.ring + 1, 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_point_names() {
+        let board = Board::<SpinorHyperbolic>::make_board(
+            TilingParameters::new::<SpinorHyperbolic>(5, 5, 4),
+        );
+        check_names(&board);
+        let center = board
+            .point_by_name(PointName { ring: 1, index: 1 })
+            .unwrap();
+        assert_eq!(board.neighbors(center).len(), 4);
+        assert_eq!(board.point_name(center).to_string(), "1-1");
+        // ring 2 starts on the positive x axis
+        let first = board.point_pos(board.point_by_name("2-1".parse().unwrap()).unwrap());
+        assert!(first.angle().abs() < 1e-6);
+
+        // a face in the middle, its corners make up the first ring
+        let board =
+            Board::<SpinorEuclidian>::make_board(TilingParameters::new::<SpinorEuclidian>(4, 4, 4));
+        check_names(&board);
+        let name_at = |col, row| board.point_name(board.point_at_grid(col, row).unwrap());
+        assert_eq!(name_at(2, 1).to_string(), "1-1");
+        assert_eq!(name_at(1, 1).to_string(), "1-2");
+        assert_eq!(name_at(3, 1).to_string(), "2-1");
+
+        let board = Board::<SpinorSpherical>::make_board(TilingParameters::new::<SpinorSpherical>(
+            11, 5, 3,
+        ));
+        assert_eq!(board.point_count(), 20);
+        check_names(&board);
+
+        // growing the board doesn't rename the points it already had
+        let params = TilingParameters::new::<SpinorHyperbolic>(5, 5, 4);
+        let small =
+            Board::<SpinorHyperbolic>::make_board(params.with_boundary(Boundary::GraphDistance(2)));
+        let large =
+            Board::<SpinorHyperbolic>::make_board(params.with_boundary(Boundary::GraphDistance(3)));
+        assert!(large.point_count() > small.point_count());
+        for i in 0..small.point_count() as i32 {
+            let j = large.point_by_name(small.point_name(i)).unwrap();
+            assert!(large.point_pos(j).distance(small.point_pos(i)) < 1e-6);
+        }
+
+        assert!("0-1".parse::<PointName>().is_err());
+        assert!("3".parse::<PointName>().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use super::*;
+
+// komi for games with handicap stones, just enough to break ties
+pub const HANDICAP_KOMI: f64 = 0.5;
+
+// 4-4 points on the larger square boards
+const MAX_EDGE_MARGIN: u32 = 3;
+
+impl<SpinorT: Spinor> Board<SpinorT> {
+    // links to the nearest edge point, None everywhere on a closed surface
+    fn edge_depths(&self) -> Option<Vec<u32>> {
+        let around_vertex = self.tiling_parameters.around_vertex as usize;
+        let mut depths = vec![u32::MAX; self.points.len()];
+        let mut queue = VecDeque::new();
+        for (i, point) in self.points.iter().enumerate() {
+            if point.neighbors.len() < around_vertex {
+                depths[i] = 0;
+                queue.push_back(i);
+            }
+        }
+        if queue.is_empty() {
+            return None;
+        }
+        while let Some(i) = queue.pop_front() {
+            for &n in &self.points[i].neighbors {
+                if depths[n as usize] == u32::MAX {
+                    depths[n as usize] = depths[i] + 1;
+                    queue.push_back(n as usize);
+                }
+            }
+        }
+        Some(depths)
+    }
+
+    // Spreads count points out by greedily maximizing the minimum distance to the
+    // points picked so far, starting from the one furthest from the center. Points
+    // stay a few links in from the edge, half the depth of the center up to the
+    // 4-4 points, so square boards get their usual star points.
+    pub fn handicap_points(&self, count: u32) -> Vec<i32> {
+        let count = (count as usize).min(self.points.len());
+        let candidates: Vec<i32> = match self.edge_depths() {
+            Some(depths) => {
+                let max_depth = depths.iter().copied().max().unwrap_or(0);
+                let mut margin = (max_depth / 2).min(MAX_EDGE_MARGIN);
+                loop {
+                    let candidates: Vec<i32> = (0..self.points.len() as i32)
+                        .filter(|&i| depths[i as usize] >= margin)
+                        .collect();
+                    if candidates.len() >= count || margin == 0 {
+                        break candidates;
+                    }
+                    margin -= 1;
+                }
+            }
+            None => (0..self.points.len() as i32).collect(),
+        };
+
+        // ties go to the lower index, so the choice is stable
+        const EPSILON: f64 = 1e-9;
+        let center = SpinorT::Point::zero();
+        let mut min_distances: Vec<f64> = candidates
+            .iter()
+            .map(|&i| self.points[i as usize].pos.distance(center))
+            .collect();
+        let mut picked = vec![];
+        while picked.len() < count {
+            let mut best = 0;
+            for k in 1..candidates.len() {
+                if min_distances[k] > min_distances[best] + EPSILON {
+                    best = k;
+                }
+            }
+            let best_idx = candidates[best];
+            let best_pos = self.points[best_idx as usize].pos;
+            picked.push(best_idx);
+            for (k, &i) in candidates.iter().enumerate() {
+                let d = if picked.len() == 1 {
+                    f64::INFINITY
+                } else {
+                    min_distances[k]
+                };
+                min_distances[k] = d.min(self.points[i as usize].pos.distance(best_pos));
+            }
+        }
+        picked
+    }
+
+    // only before the first move, the stones become part of the starting position
+    pub(super) fn add_setup_stone(&mut self, idx: i32, ty: StoneType) {
+        debug_assert!(self.tree.children(ROOT_NODE).is_empty());
+        self.place_stone(idx, ty);
+        self.setup_stones.push(idx);
+        self.initial_hash = self.hash;
+        self.rebuild_seen_hashes();
+    }
+
+    pub fn setup_stones(&self) -> &[i32] {
+        &self.setup_stones
+    }
+}
+
+impl<SpinorT: Spinor> GameState<SpinorT> {
+    // black places the handicap stones anywhere, one per click, before white moves
+    pub fn with_free_handicap(tiling_parameters: TilingParameters, handicap: u32) -> Self {
+        let mut game = Self::new(tiling_parameters, 0);
+        if handicap > 0 {
+            game.handicap = handicap;
+            game.handicap_to_place = handicap;
+            game.komi = HANDICAP_KOMI;
+        }
+        game
+    }
+
+    pub(super) fn place_fixed_handicap(&mut self, handicap: u32) {
+        if handicap == 0 {
+            return;
+        }
+        self.handicap = handicap;
+        self.komi = HANDICAP_KOMI;
+        for idx in self.board.handicap_points(handicap) {
+            self.board.add_setup_stone(idx, StoneType::Black);
+        }
+        self.start_after_handicap();
+    }
+
+    pub fn handicap_to_place(&self) -> u32 {
+        self.handicap_to_place
+    }
+
+    pub fn place_handicap_stone(&mut self, idx: i32) -> bool {
+        if self.handicap_to_place == 0 || self.board.points[idx as usize].ty != StoneType::Empty {
+            return false;
+        }
+        self.board.add_setup_stone(idx, StoneType::Black);
+        self.handicap_to_place -= 1;
+        if self.handicap_to_place == 0 {
+            self.start_after_handicap();
+        }
+        self.needs_render = true;
+        true
+    }
+
+    pub(super) fn start_after_handicap(&mut self) {
+        self.board.initial_turn = Turn::White;
+        self.board.rebuild_seen_hashes();
+        self.turn = Turn::White;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    #[test]
+    fn test_square_star_points() {
+        let params = TilingParameters::new::<SpinorEuclidian>(19, 4, 4);
+        let game = GameState::<SpinorEuclidian>::new(params, 5);
+        let mut points: Vec<(u32, u32)> = game
+            .board()
+            .setup_stones()
+            .iter()
+            .map(|&i| game.grid_coords(i).unwrap())
+            .collect();
+        points.sort();
+        assert_eq!(points, [(3, 3), (3, 15), (9, 9), (15, 3), (15, 15)]);
+        assert_eq!(game.turn(), Turn::White);
+
+        let params = TilingParameters::new::<SpinorEuclidian>(9, 4, 4);
+        let board = Board::<SpinorEuclidian>::make_board(params);
+        for idx in board.handicap_points(4) {
+            let (col, row) = board.grid_coords(idx).unwrap();
+            assert!(col == 2 || col == 6);
+            assert!(row == 2 || row == 6);
+        }
+    }
+
+    #[test]
+    fn test_hyperbolic_handicap() {
+        let params = TilingParameters::new::<SpinorHyperbolic>(7, 5, 4);
+        let board = Board::<SpinorHyperbolic>::make_board(params);
+        let points = board.handicap_points(6);
+        assert_eq!(points.len(), 6);
+        let depths = board.edge_depths().unwrap();
+        for &a in &points {
+            assert!(depths[a as usize] > 0);
+            for &b in &points {
+                assert!(a == b || board.point_pos(a).distance(board.point_pos(b)) > 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_free_handicap() {
+        let params = TilingParameters::new::<SpinorEuclidian>(9, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::with_free_handicap(params, 2);
+        assert!(game.play_point(0));
+        assert!(!game.play_point(0));
+        assert_eq!(game.turn(), Turn::Black);
+        assert!(game.play_point(1));
+        assert_eq!(game.turn(), Turn::White);
+        assert_eq!(game.handicap_to_place(), 0);
+        assert!(game.moves().is_empty());
+
+        assert!(game.play_point(2));
+        game.go_to_parent();
+        assert_eq!(game.board().stone(1), StoneType::Black);
+        assert_eq!(game.turn(), Turn::White);
+    }
+}
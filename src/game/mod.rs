@@ -1,13 +1,37 @@
-use std::{f64::consts::PI, marker::PhantomData, ptr};
+use std::{
+    collections::{HashSet, VecDeque},
+    f64::consts::PI,
+    fmt,
+    marker::PhantomData,
+    ptr,
+    str::FromStr,
+};
 
-use cgmath::{abs_diff_eq, relative_eq, MetricSpace, Vector2, Zero};
+use cgmath::{abs_diff_eq, relative_eq, MetricSpace, Vector2, Vector3, Zero};
 use log::info;
 
+mod chains;
+mod clock;
+mod coords;
+mod handicap;
+mod player;
+#[cfg(feature = "gui")]
 pub mod render;
+pub mod search;
+pub mod sgf;
+pub mod tree;
+mod zobrist;
+pub use clock::*;
+pub use coords::*;
+use handicap::*;
 use more_asserts::assert_ge;
+pub use player::*;
+#[cfg(feature = "gui")]
 use render::*;
+use tree::*;
+use zobrist::*;
 
-use crate::geometry::*;
+use crate::geometry::{spatial::PointIndex, *};
 
 /* struct PanicIterator<T> {
     phantom: PhantomData<T>,
@@ -33,7 +57,159 @@ pub const MAX_STONES: u64 = 1024 * 16;
 pub const STONE_RADIUS: f64 = 0.4;
 
 #[derive(Clone, Copy, PartialEq, Debug)]
-enum StoneType {
+pub enum KoRule {
+    // only forbids immediately recreating the previous position
+    Simple,
+    // forbids recreating any earlier board position
+    PositionalSuperko,
+    // same, but only if the same player is to move
+    SituationalSuperko,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rules {
+    pub ko_rule: KoRule,
+    pub scoring_rule: ScoringRule,
+    // multi stone suicide, single stones would only recreate the position
+    pub allow_suicide: bool,
+}
+
+impl Rules {
+    pub fn chinese() -> Self {
+        Self {
+            ko_rule: KoRule::PositionalSuperko,
+            scoring_rule: ScoringRule::Area,
+            allow_suicide: false,
+        }
+    }
+
+    pub fn japanese() -> Self {
+        Self {
+            ko_rule: KoRule::Simple,
+            scoring_rule: ScoringRule::Territory,
+            allow_suicide: false,
+        }
+    }
+
+    pub fn aga() -> Self {
+        Self {
+            ko_rule: KoRule::SituationalSuperko,
+            scoring_rule: ScoringRule::Aga,
+            allow_suicide: false,
+        }
+    }
+
+    pub fn new_zealand() -> Self {
+        Self {
+            ko_rule: KoRule::SituationalSuperko,
+            scoring_rule: ScoringRule::Area,
+            allow_suicide: true,
+        }
+    }
+
+    pub fn tromp_taylor() -> Self {
+        Self {
+            ko_rule: KoRule::PositionalSuperko,
+            scoring_rule: ScoringRule::Area,
+            allow_suicide: true,
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::chinese()
+    }
+}
+
+// for the sgf RU property, "area:situational-superko:suicide", the usual rule set
+// names like "Japanese" or "NZ" are read as well
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scoring = match self.scoring_rule {
+            ScoringRule::Area => "area",
+            ScoringRule::Territory => "territory",
+            ScoringRule::Aga => "aga",
+        };
+        let ko = match self.ko_rule {
+            KoRule::Simple => "simple",
+            KoRule::PositionalSuperko => "positional-superko",
+            KoRule::SituationalSuperko => "situational-superko",
+        };
+        let suicide = if self.allow_suicide {
+            "suicide"
+        } else {
+            "no-suicide"
+        };
+        write!(f, "{}:{}:{}", scoring, ko, suicide)
+    }
+}
+
+impl FromStr for Rules {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "chinese" => return Ok(Rules::chinese()),
+            "japanese" => return Ok(Rules::japanese()),
+            "aga" => return Ok(Rules::aga()),
+            "nz" | "new zealand" => return Ok(Rules::new_zealand()),
+            "tromp-taylor" | "tromp taylor" => return Ok(Rules::tromp_taylor()),
+            _ => {}
+        }
+        let mut parts = s.trim().split(':');
+        let scoring_rule = match parts.next() {
+            Some("area") => ScoringRule::Area,
+            Some("territory") => ScoringRule::Territory,
+            Some("aga") => ScoringRule::Aga,
+            _ => return Err(()),
+        };
+        let ko_rule = match parts.next() {
+            Some("simple") => KoRule::Simple,
+            Some("positional-superko") => KoRule::PositionalSuperko,
+            Some("situational-superko") => KoRule::SituationalSuperko,
+            _ => return Err(()),
+        };
+        let allow_suicide = match parts.next() {
+            Some("suicide") => true,
+            Some("no-suicide") => false,
+            _ => return Err(()),
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(Rules {
+            ko_rule,
+            scoring_rule,
+            allow_suicide,
+        })
+    }
+}
+
+// the rule sets above by name, for the command line
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum RulesPreset {
+    Chinese,
+    Japanese,
+    Aga,
+    NewZealand,
+    TrompTaylor,
+}
+
+impl RulesPreset {
+    pub fn rules(self) -> Rules {
+        match self {
+            RulesPreset::Chinese => Rules::chinese(),
+            RulesPreset::Japanese => Rules::japanese(),
+            RulesPreset::Aga => Rules::aga(),
+            RulesPreset::NewZealand => Rules::new_zealand(),
+            RulesPreset::TrompTaylor => Rules::tromp_taylor(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StoneType {
     Empty,
     Black,
     White,
@@ -47,30 +223,246 @@ struct BoardPoint<SpinorT: Spinor> {
     neighbors: Vec<i32>,
     ty: StoneType,
     reversed: bool,
+
+    // chain bookkeeping, see chains.rs
+    chain: i32,
+    next_stone: i32,
+    chain_size: u32,
+    pseudo_liberties: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Move {
+    Place(i32),
+    Pass,
+}
+
+#[derive(Clone, Debug)]
+pub struct MoveRecord {
+    pub mv: Move,
+    pub player: Turn,
+    pub captured: Vec<i32>,
+    // the mover's own stones, when suicide is allowed
+    pub self_captured: Vec<i32>,
+    // stone hash of the position after this move
+    hash: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScoringRule {
+    // stones plus surrounded points, as in chinese and tromp-taylor rules
+    Area,
+    // surrounded points plus prisoners, as in japanese rules
+    Territory,
+    // territory counting where each pass hands the opponent a prisoner, which
+    // makes it agree with area counting
+    Aga,
+}
+
+impl ScoringRule {
+    pub fn next(self) -> Self {
+        match self {
+            ScoringRule::Area => ScoringRule::Territory,
+            ScoringRule::Territory => ScoringRule::Aga,
+            ScoringRule::Aga => ScoringRule::Area,
+        }
+    }
+
+    pub fn default_komi(self) -> f64 {
+        match self {
+            ScoringRule::Area | ScoringRule::Aga => 7.5,
+            ScoringRule::Territory => 6.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PlayerScore {
+    pub stones: i32,
+    // surrounded empty points, and points of dead stones
+    pub territory: i32,
+    // captures, dead stones and under aga rules the opponent's passes
+    pub prisoners: i32,
+    pub komi: f64,
+    pub total: f64,
 }
 
 pub struct ScoreState {
+    // who each point counts for, Empty for neutral points
     territory: Vec<StoneType>,
-    pub black_score: i32,
-    pub white_score: i32,
+    // stones marked dead, scored as part of the surrounding area
+    dead: Vec<bool>,
+    pub rule: ScoringRule,
+    pub black: PlayerScore,
+    pub white: PlayerScore,
+    pub black_confirmed: bool,
+    pub white_confirmed: bool,
+}
+
+impl ScoreState {
+    pub fn is_dead(&self, idx: i32) -> bool {
+        self.dead[idx as usize]
+    }
+
+    pub fn dead_stones(&self) -> Vec<i32> {
+        (0..self.dead.len() as i32)
+            .filter(|&i| self.dead[i as usize])
+            .collect()
+    }
+
+    // the player to move confirms first, then the other one
+    pub fn next_to_confirm(&self, turn: Turn) -> Turn {
+        let confirmed = match turn {
+            Turn::Black => self.black_confirmed,
+            Turn::White => self.white_confirmed,
+        };
+        if confirmed {
+            turn.other()
+        } else {
+            turn
+        }
+    }
+
+    // positive when black is ahead
+    pub fn margin(&self) -> f64 {
+        self.black.total - self.white.total
+    }
+}
+
+// score both players agreed on after marking dead stones
+#[derive(Clone, Debug, PartialEq)]
+pub struct FinalScore {
+    pub rule: ScoringRule,
+    pub black: PlayerScore,
+    pub white: PlayerScore,
+    pub dead: Vec<i32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GamePhase {
+    Playing,
+    // after two passes, or on request, while dead stones get marked
+    Scoring,
+    // no more moves, see GameState::result
+    Finished,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResultReason {
+    // by this many points
+    Score(f64),
+    Resignation,
+    Timeout,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameResult {
+    // None for a draw
+    pub winner: Option<Turn>,
+    pub reason: ResultReason,
+}
+
+// same notation as the sgf RE property, "B+3.5", "W+R", "B+T" or "0"
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(winner) = self.winner else {
+            return f.write_str("0");
+        };
+        let winner = match winner {
+            Turn::Black => "B",
+            Turn::White => "W",
+        };
+        match self.reason {
+            ResultReason::Score(margin) => write!(f, "{}+{}", winner, margin),
+            ResultReason::Resignation => write!(f, "{}+R", winner),
+            ResultReason::Timeout => write!(f, "{}+T", winner),
+        }
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if matches!(s, "0" | "Draw" | "Jigo") {
+            return Ok(GameResult {
+                winner: None,
+                reason: ResultReason::Score(0.0),
+            });
+        }
+        let (winner, reason) = s.split_once('+').ok_or(())?;
+        let winner = match winner {
+            "B" => Turn::Black,
+            "W" => Turn::White,
+            _ => return Err(()),
+        };
+        let reason = match reason {
+            "R" | "Resign" => ResultReason::Resignation,
+            "T" | "Time" => ResultReason::Timeout,
+            margin => ResultReason::Score(margin.parse().map_err(|_| ())?),
+        };
+        Ok(GameResult {
+            winner: Some(winner),
+            reason,
+        })
+    }
+}
+
+// why a stone can't go on a point
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IllegalMove {
+    Occupied,
+    // single stones, or any group where the rules don't allow suicide
+    Suicide,
+    // recreates the position before the opponent's last move
+    Ko,
+    // recreates an earlier position, under the superko rules
+    Superko,
+    GameOver,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            IllegalMove::Occupied => "point is occupied",
+            IllegalMove::Suicide => "suicide",
+            IllegalMove::Ko => "ko",
+            IllegalMove::Superko => "superko",
+            IllegalMove::GameOver => "game is over",
+        };
+        f.write_str(reason)
+    }
 }
 
-struct Board<SpinorT: Spinor> {
+pub struct Board<SpinorT: Spinor> {
     points: Vec<BoardPoint<SpinorT>>,
     links: Vec<(i32, i32)>,
-    // TODO consider a delta history rather than copies
-    // also consider a packed board representation
-    // also see "zobrist hashing"
-    history: Vec<Vec<StoneType>>,
-    history_idx: i32,
+    index: PointIndex<SpinorT::Point>,
+    // TODO consider a packed board representation
+    tree: GameTree,
+    initial_turn: Turn,
+    // handicap stones placed before the first move, and the hash with them on
+    setup_stones: Vec<i32>,
+    initial_hash: u64,
     tiling_parameters: TilingParameters,
+
+    zobrist: ZobristTable,
+    hash: u64,
+    // hashes of every position up to and including the current one
+    seen_positions: HashSet<u64>,
+    seen_situations: HashSet<u64>,
+    // see coords.rs
+    names: Vec<PointName>,
 }
 
 impl<SpinorT: Spinor> Board<SpinorT> {
-    fn make_board(tiling_parameters: TilingParameters) -> Self {
-        // TODO support even size probably?
-        assert!(tiling_parameters.edge_count % 2 == 1);
-
+    pub fn make_board(tiling_parameters: TilingParameters) -> Self {
+        // the boundary parser rejects this, a board needs somewhere to play
+        assert_ne!(
+            tiling_parameters.boundary,
+            Boundary::PointCount(0),
+            "board with no points"
+        );
         let neighbor_directions: Vec<SpinorT> = (0..tiling_parameters.around_vertex)
             .map(|i| {
                 SpinorT::translation(
@@ -83,9 +475,17 @@ impl<SpinorT: Spinor> Board<SpinorT> {
         let mut board = Self {
             points: Vec::new(),
             links: Vec::new(),
-            history: Vec::new(),
-            history_idx: 0,
+            index: PointIndex::new(),
+            tree: GameTree::new(),
+            initial_turn: Turn::Black,
+            setup_stones: Vec::new(),
+            initial_hash: 0,
             tiling_parameters,
+            zobrist: ZobristTable::new(0),
+            hash: 0,
+            seen_positions: HashSet::new(),
+            seen_situations: HashSet::new(),
+            names: Vec::new(),
         };
 
         let mut test_count = 1;
@@ -99,52 +499,216 @@ impl<SpinorT: Spinor> Board<SpinorT> {
             SpinorT::one(),
             false,
         );
+        match tiling_parameters.center {
+            BoardCenter::Vertex => {}
+            BoardCenter::Edge => board.add_point(
+                &neighbor_directions,
+                &reverse_neighbor_directions,
+                neighbor_directions[0],
+                true,
+            ),
+            BoardCenter::Face => {
+                board.walk_face(0, 0, &neighbor_directions, &reverse_neighbor_directions);
+            }
+        }
+        let center_count = board.points.len();
+        let center_pos = board.center_pos(center_count);
+
+        let boundary = tiling_parameters.boundary;
         let mut start_i = 0;
-        'outer: for _ring in 1..(tiling_parameters.edge_count / 2 + 1) {
+        'outer: for ring in 1.. {
+            match boundary {
+                Boundary::Rings if ring > tiling_parameters.edge_count.saturating_sub(1) / 2 => {
+                    break
+                }
+                Boundary::GraphDistance(d) if ring > d => break,
+                _ => {}
+            }
             let l = board.points.len();
             for i in start_i..l {
                 for j in 0..neighbor_directions.len() {
-                    let mut cur_transform = board.points[i].transform;
-                    for (k, &dir) in neighbor_directions.iter().cycle().skip(j).enumerate() {
-                        let link_reversed = (k % 2 == 1) ^ board.points[i].reversed;
-                        cur_transform = if link_reversed {
-                            cur_transform * dir.reverse()
-                        } else {
-                            cur_transform * dir
-                        };
-                        cur_transform.normalize();
-                        let pos = cur_transform.apply(SpinorT::Point::zero());
-                        if board.find_point(pos, 1e-3) != -1 {
-                            if k == 0 {
-                                continue;
-                            } else {
-                                break;
-                            }
-                        }
-                        board.add_point(
-                            &neighbor_directions,
-                            &reverse_neighbor_directions,
-                            cur_transform,
-                            !link_reversed,
-                        );
-
-                        test_count += 1;
-                        if test_count >= 25000000 {
-                            break 'outer;
-                        }
+                    test_count +=
+                        board.walk_face(i, j, &neighbor_directions, &reverse_neighbor_directions);
+                    if test_count >= 25000000 {
+                        break 'outer;
                     }
                 }
             }
+            // closed surface
+            if board.points.len() == l {
+                break;
+            }
             start_i = l;
+
+            // rings only move outwards, so stop once a whole ring is past the cutoff
+            let nearest_new = board.points[l..]
+                .iter()
+                .map(|p| p.pos.distance(center_pos))
+                .fold(f64::INFINITY, f64::min);
+            let cutoff = match boundary {
+                Boundary::Radius(r) => r,
+                Boundary::PointCount(n) if board.points.len() >= n as usize => {
+                    let mut distances: Vec<f64> = board
+                        .points
+                        .iter()
+                        .map(|p| p.pos.distance(center_pos))
+                        .collect();
+                    distances.sort_by(f64::total_cmp);
+                    distances[n as usize - 1]
+                }
+                _ => f64::INFINITY,
+            };
+            if nearest_new > cutoff {
+                break;
+            }
         }
 
-        board
-            .history
-            .push(vec![StoneType::Empty; board.points.len()]);
+        let keep = board.points_in_boundary(center_count, center_pos);
+        board.retain_points(&keep);
+        if tiling_parameters.center != BoardCenter::Vertex {
+            board.recenter(SpinorT::translation_to(center_pos).reverse());
+        }
+        board.assign_names();
+
+        board.zobrist = ZobristTable::new(board.points.len());
+        board.rebuild_seen_hashes();
 
         board
     }
 
+    // adds the points around a face of point i, starting out in direction j and
+    // stopping at the first point that already exists, returns how many were added
+    fn walk_face(
+        &mut self,
+        i: usize,
+        j: usize,
+        neighbor_directions: &Vec<SpinorT>,
+        reverse_neighbor_directions: &Vec<SpinorT>,
+    ) -> u32 {
+        let mut added = 0;
+        let mut cur_transform = self.points[i].transform;
+        for (k, &dir) in neighbor_directions.iter().cycle().skip(j).enumerate() {
+            let link_reversed = (k % 2 == 1) ^ self.points[i].reversed;
+            cur_transform = if link_reversed {
+                cur_transform * dir.reverse()
+            } else {
+                cur_transform * dir
+            };
+            cur_transform.normalize();
+            let pos = cur_transform.apply(SpinorT::Point::zero());
+            if self.find_point(pos, 1e-3) != -1 {
+                if k == 0 {
+                    continue;
+                } else {
+                    break;
+                }
+            }
+            self.add_point(
+                neighbor_directions,
+                reverse_neighbor_directions,
+                cur_transform,
+                !link_reversed,
+            );
+            added += 1;
+        }
+        added
+    }
+
+    // middle of the first center_count points, which are all the same distance from it
+    fn center_pos(&self, center_count: usize) -> SpinorT::Point {
+        let sum = self.points[..center_count]
+            .iter()
+            .map(|p| p.pos.to_projective::<f64>())
+            .fold(Vector3::zero(), |a, b| a + b);
+        SpinorT::Point::from_flat(sum.x / sum.z, sum.y / sum.z)
+    }
+
+    fn points_in_boundary(&self, center_count: usize, center_pos: SpinorT::Point) -> Vec<bool> {
+        let distances: Vec<f64> = self
+            .points
+            .iter()
+            .map(|p| p.pos.distance(center_pos))
+            .collect();
+        match self.tiling_parameters.boundary {
+            Boundary::Rings => vec![true; self.points.len()],
+            // a little slack for points exactly on the boundary
+            Boundary::Radius(r) => distances.iter().map(|&d| d <= r + 1e-6).collect(),
+            Boundary::GraphDistance(max_depth) => {
+                let mut depths = vec![u32::MAX; self.points.len()];
+                let mut queue: VecDeque<usize> = (0..center_count).collect();
+                depths[..center_count].fill(0);
+                while let Some(i) = queue.pop_front() {
+                    for &n in &self.points[i].neighbors {
+                        if depths[n as usize] == u32::MAX {
+                            depths[n as usize] = depths[i] + 1;
+                            queue.push_back(n as usize);
+                        }
+                    }
+                }
+                depths.iter().map(|&d| d <= max_depth).collect()
+            }
+            Boundary::PointCount(n) => {
+                let mut order: Vec<usize> = (0..self.points.len()).collect();
+                order.sort_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+                let mut keep = vec![false; self.points.len()];
+                for &i in order.iter().take(n as usize) {
+                    keep[i] = true;
+                }
+                keep
+            }
+        }
+    }
+
+    fn retain_points(&mut self, keep: &[bool]) {
+        let mut new_idx = vec![-1; self.points.len()];
+        let mut count = 0;
+        for (i, &k) in keep.iter().enumerate() {
+            if k {
+                new_idx[i] = count;
+                count += 1;
+            }
+        }
+        let points = std::mem::take(&mut self.points);
+        self.points = points
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, &k)| k)
+            .map(|(mut point, _)| {
+                point.neighbors = point
+                    .neighbors
+                    .iter()
+                    .map(|&n| new_idx[n as usize])
+                    .filter(|&n| n >= 0)
+                    .collect();
+                point
+            })
+            .collect();
+        self.links = self
+            .links
+            .iter()
+            .map(|&(a, b)| (new_idx[a as usize], new_idx[b as usize]))
+            .filter(|&(a, b)| a >= 0 && b >= 0)
+            .collect();
+        self.rebuild_index();
+    }
+
+    fn recenter(&mut self, transform: SpinorT) {
+        for point in self.points.iter_mut() {
+            point.transform = transform * point.transform;
+            point.transform.normalize();
+            point.relative_transform = point.transform;
+            point.pos = point.transform.apply(SpinorT::Point::zero());
+        }
+        self.rebuild_index();
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index = PointIndex::new();
+        for (i, point) in self.points.iter().enumerate() {
+            self.index.insert(point.pos, i as i32);
+        }
+    }
+
     fn add_point(
         &mut self,
         neighbor_directions: &Vec<SpinorT>,
@@ -159,6 +723,10 @@ impl<SpinorT: Spinor> Board<SpinorT> {
             neighbors: Vec::new(),
             ty: StoneType::Empty,
             reversed,
+            chain: -1,
+            next_stone: -1,
+            chain_size: 0,
+            pseudo_liberties: 0,
         };
         let this_idx = self.points.len() as i32;
 
@@ -180,17 +748,90 @@ impl<SpinorT: Spinor> Board<SpinorT> {
                 info!("adding link {:?}", self.links.last().unwrap());
             }
         }
+        self.index.insert(point.pos, this_idx);
         self.points.push(point);
     }
 
-    // TODO use some kind of spatial data structure for this?
-    fn find_point(&self, pos: SpinorT::Point, dist: f64) -> i32 {
-        for (i, point) in self.points.iter().enumerate() {
-            if pos.distance(point.pos) <= dist {
-                return i as i32;
-            }
+    pub fn point_count(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn stone(&self, idx: i32) -> StoneType {
+        self.points[idx as usize].ty
+    }
+
+    pub fn neighbors(&self, idx: i32) -> &[i32] {
+        &self.points[idx as usize].neighbors
+    }
+
+    pub fn point_pos(&self, idx: i32) -> SpinorT::Point {
+        self.points[idx as usize].pos
+    }
+
+    // relative to the floating origin, as handed to the renderer
+    pub fn relative_transform(&self, idx: i32) -> SpinorT {
+        self.points[idx as usize].relative_transform
+    }
+
+    pub fn links(&self) -> &[(i32, i32)] {
+        &self.links
+    }
+
+    pub fn tiling_parameters(&self) -> TilingParameters {
+        self.tiling_parameters
+    }
+
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    // lowest index within dist of pos, or -1
+    pub fn find_point(&self, pos: SpinorT::Point, dist: f64) -> i32 {
+        self.index.find(pos, dist)
+    }
+
+    // about the size of a stone, but never reaching halfway to a neighbor so the
+    // pick can't be ambiguous
+    pub fn pick_radius(&self) -> f64 {
+        let params = &self.tiling_parameters;
+        (STONE_RADIUS * params.stone_scale).min(0.5 * params.distance)
+    }
+
+    pub fn pick_point(&self, pos: SpinorT::Point) -> Option<i32> {
+        let idx = self.find_point(pos, self.pick_radius());
+        (idx >= 0).then_some(idx)
+    }
+
+    // grid coordinates only make sense on square boards
+    fn is_square_grid(&self) -> bool {
+        let params = &self.tiling_parameters;
+        SpinorT::geometry() == Geometry::Euclidian
+            && params.sides == 4
+            && params.around_vertex == 4
+            && params.boundary == Boundary::Rings
+            && params.center == BoardCenter::for_edge_count(params.edge_count)
+    }
+
+    // (column, row) counted from the top left corner
+    fn grid_coords(&self, idx: i32) -> Option<(u32, u32)> {
+        if !self.is_square_grid() {
+            return None;
+        }
+        let half = 0.5 * (self.tiling_parameters.edge_count as f64 - 1.0);
+        let v = self.points[idx as usize].pos.to_projective::<f64>();
+        let col = (v.x / v.z + half).round() as u32;
+        let row = (half - v.y / v.z).round() as u32;
+        Some((col, row))
+    }
+
+    fn point_at_grid(&self, col: u32, row: u32) -> Option<i32> {
+        if !self.is_square_grid() {
+            return None;
         }
-        -1
+        let half = 0.5 * (self.tiling_parameters.edge_count as f64 - 1.0);
+        let pos = SpinorT::Point::from_flat(col as f64 - half, half - row as f64);
+        let idx = self.find_point(pos, 0.1);
+        (idx >= 0).then_some(idx)
     }
 
     fn update_floating_origin(&mut self, camera_r: &SpinorT) {
@@ -199,191 +840,489 @@ impl<SpinorT: Spinor> Board<SpinorT> {
         }
     }
 
-    fn save_move(&mut self) {
-        self.history_idx += 1;
-        self.history.truncate(self.history_idx as usize);
-        self.history
-            .push(self.points.iter_mut().map(|p| p.ty).collect());
+    fn set_stone(&mut self, idx: i32, ty: StoneType) {
+        let point = &mut self.points[idx as usize];
+        self.hash ^= self.zobrist.stone(idx, point.ty) ^ self.zobrist.stone(idx, ty);
+        point.ty = ty;
     }
 
-    fn move_history(&mut self, offset: i32) -> i32 {
-        self.history_idx += offset;
-        if self.history_idx < 0 || self.history_idx >= self.history.len() as i32 {
-            self.history_idx -= offset;
-            return 0;
+    fn apply_move(&mut self, record: &MoveRecord) {
+        if let Move::Place(idx) = record.mv {
+            self.place_stone(idx, record.player.stone());
+        }
+        for &i in record.captured.iter().chain(&record.self_captured) {
+            if self.points[i as usize].ty != StoneType::Empty {
+                self.remove_chain(i);
+            }
+        }
+    }
+
+    fn unapply_move(&mut self, record: &MoveRecord) {
+        for &i in &record.self_captured {
+            self.place_stone(i, record.player.stone());
+        }
+        for &i in &record.captured {
+            self.place_stone(i, record.player.other().stone());
+        }
+        if let Move::Place(idx) = record.mv {
+            self.remove_stone(idx);
+        }
+    }
+
+    // record a move that has already been applied to the board
+    fn save_move(&mut self, record: MoveRecord) {
+        debug_assert_eq!(record.hash, self.hash);
+        self.seen_positions.insert(record.hash);
+        self.seen_situations
+            .insert(self.zobrist.situation(record.hash, record.player.other()));
+        let node = self.tree.add_child(self.tree.current(), record);
+        self.tree.set_current(node);
+    }
+
+    fn go_to_node(&mut self, target: usize) {
+        let mut from = self.tree.current();
+        let mut to = target;
+        let mut apply_nodes = vec![];
+        while self.tree.depth(to) > self.tree.depth(from) {
+            apply_nodes.push(to);
+            to = self.tree.parent(to).unwrap();
+        }
+        while from != to {
+            let record = self.tree.record(from).unwrap().clone();
+            self.unapply_move(&record);
+            from = self.tree.parent(from).unwrap();
+            if self.tree.depth(to) > self.tree.depth(from) {
+                apply_nodes.push(to);
+                to = self.tree.parent(to).unwrap();
+            }
+        }
+        for &node in apply_nodes.iter().rev() {
+            let record = self.tree.record(node).unwrap().clone();
+            self.apply_move(&record);
         }
-        for (i, p) in self.points.iter_mut().enumerate() {
-            p.ty = self.history[self.history_idx as usize][i];
+        self.tree.set_current(target);
+        self.rebuild_seen_hashes();
+    }
+
+    fn move_history(&mut self, offset: i32) -> i32 {
+        let mut target = self.tree.current();
+        for _ in 0..offset.abs() {
+            let next = if offset < 0 {
+                self.tree.parent(target)
+            } else {
+                self.tree.selected_child(target)
+            };
+            match next {
+                Some(node) => target = node,
+                None => return 0,
+            }
         }
+        self.go_to_node(target);
         offset
     }
 
-    // TODO superko?
-    fn last_move_is_ko(&self) -> bool {
-        if self.history_idx < 2 {
+    fn move_variation(&mut self, offset: i32) -> bool {
+        let current = self.tree.current();
+        let Some(parent) = self.tree.parent(current) else {
             return false;
+        };
+        let siblings = self.tree.children(parent);
+        let idx = self.tree.sibling_idx(current) as i32 + offset;
+        if idx < 0 || idx >= siblings.len() as i32 {
+            return false;
+        }
+        self.go_to_node(siblings[idx as usize]);
+        true
+    }
+
+    fn go_to_mainline(&mut self) {
+        let mut target = self.tree.current();
+        while !self.tree.is_mainline(target) {
+            target = self.tree.parent(target).unwrap();
+        }
+        self.go_to_node(target);
+    }
+
+    // number of moves leading up to the current position
+    fn history_idx(&self) -> i32 {
+        self.tree.depth(self.tree.current())
+    }
+
+    // (prisoners taken, passes made) by each player up to the current position,
+    // indexed by Turn
+    fn move_counts(&self) -> ([u32; 2], [u32; 2]) {
+        let mut prisoners = [0; 2];
+        let mut passes = [0; 2];
+        for node in self.tree.path_to(self.tree.current()) {
+            let record = self.tree.record(node).unwrap();
+            prisoners[record.player as usize] += record.captured.len() as u32;
+            prisoners[record.player.other() as usize] += record.self_captured.len() as u32;
+            if record.mv == Move::Pass {
+                passes[record.player as usize] += 1;
+            }
+        }
+        (prisoners, passes)
+    }
+
+    // stone hash and player to move at a tree node
+    fn hash_at(&self, node: usize) -> (u64, Turn) {
+        match self.tree.record(node) {
+            Some(record) => (record.hash, record.player.other()),
+            None => (self.initial_hash, self.initial_turn),
+        }
+    }
+
+    fn next_turn(&self) -> Turn {
+        self.hash_at(self.tree.current()).1
+    }
+
+    fn rebuild_seen_hashes(&mut self) {
+        self.seen_positions.clear();
+        self.seen_situations.clear();
+        let mut node = Some(self.tree.current());
+        while let Some(n) = node {
+            let (hash, turn) = self.hash_at(n);
+            self.seen_positions.insert(hash);
+            self.seen_situations
+                .insert(self.zobrist.situation(hash, turn));
+            node = self.tree.parent(n);
+        }
+    }
+
+    // checks a position, played or not, against history
+    fn repeats_hash(&self, ko_rule: KoRule, hash: u64, next_turn: Turn) -> bool {
+        match ko_rule {
+            KoRule::Simple => match self.tree.parent(self.tree.current()) {
+                Some(parent) => self.hash_at(parent).0 == hash,
+                None => false,
+            },
+            KoRule::PositionalSuperko => self.seen_positions.contains(&hash),
+            KoRule::SituationalSuperko => self
+                .seen_situations
+                .contains(&self.zobrist.situation(hash, next_turn)),
+        }
+    }
+
+    // repeating the position before the opponent's move is reported as a ko, even
+    // under the superko rules
+    fn check_repetition(
+        &self,
+        ko_rule: KoRule,
+        hash: u64,
+        next_turn: Turn,
+    ) -> Result<(), IllegalMove> {
+        if !self.repeats_hash(ko_rule, hash, next_turn) {
+            Ok(())
+        } else if self.repeats_hash(KoRule::Simple, hash, next_turn) {
+            Err(IllegalMove::Ko)
+        } else {
+            Err(IllegalMove::Superko)
         }
-        self.history[self.history_idx as usize] == self.history[self.history_idx as usize - 2]
     }
 }
 
-enum Turn {
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Turn {
     Black,
     White,
 }
 
+impl Turn {
+    pub fn other(self) -> Self {
+        match self {
+            Turn::Black => Turn::White,
+            Turn::White => Turn::Black,
+        }
+    }
+
+    fn stone(self) -> StoneType {
+        match self {
+            Turn::Black => StoneType::Black,
+            Turn::White => StoneType::White,
+        }
+    }
+
+    fn from_stone(ty: StoneType) -> Self {
+        match ty {
+            StoneType::Black => Turn::Black,
+            StoneType::White => Turn::White,
+            StoneType::Empty => panic!("empty point has no player"),
+        }
+    }
+}
+
 pub struct GameState<SpinorT: Spinor> {
     board: Board<SpinorT>,
     turn: Turn,
+    pub rules: Rules,
+    // added to white's score
+    pub komi: f64,
+    pub handicap: u32,
+    // stones black still has to place in free handicap games
+    handicap_to_place: u32,
     pub hover_idx: i32,
     pub needs_render: bool,
 
     pub score: Option<ScoreState>,
+    pub final_score: Option<FinalScore>,
+    pub result: Option<GameResult>,
+    pub clock: Option<GameClock>,
+    pub ownership: Option<search::Ownership>,
+    // why the last clicked point couldn't be played, until the next move
+    pub illegal_move: Option<IllegalMove>,
 }
 
 impl<SpinorT: Spinor> GameState<SpinorT> {
-    pub fn new(tiling_parameters: TilingParameters) -> Self {
+    // handicap stones go on Board::handicap_points, and white moves first
+    pub fn new(tiling_parameters: TilingParameters, handicap: u32) -> Self {
         let board = Board::make_board(tiling_parameters);
-        Self {
+        let mut game = Self {
             board,
             turn: Turn::Black,
+            rules: Rules::default(),
+            komi: Rules::default().scoring_rule.default_komi(),
+            handicap: 0,
+            handicap_to_place: 0,
             hover_idx: -1,
             needs_render: true,
             score: None,
+            final_score: None,
+            result: None,
+            clock: None,
+            ownership: None,
+            illegal_move: None,
+        };
+        game.place_fixed_handicap(handicap);
+        game
+    }
+
+    fn update_captures(&mut self, point_idx: i32) -> Vec<i32> {
+        let captured_type = self.turn.other().stone();
+        let mut captured_idxs = vec![];
+        for k in 0..self.board.points[point_idx as usize].neighbors.len() {
+            let n = self.board.points[point_idx as usize].neighbors[k];
+            if self.board.points[n as usize].ty == captured_type && !self.board.has_liberties(n) {
+                captured_idxs.append(&mut self.board.remove_chain(n));
+            }
+        }
+        captured_idxs
+    }
+
+    // after the opponent's captures, takes off the mover's own group if it has no
+    // liberties left
+    fn update_self_captures(&mut self, point_idx: i32) -> Vec<i32> {
+        if self.board.has_liberties(point_idx) {
+            vec![]
+        } else {
+            self.board.remove_chain(point_idx)
         }
     }
 
-    fn update_captures(&mut self, point_idx: i32) -> bool {
-        let captured_type = match self.turn {
-            Turn::Black => StoneType::White,
-            Turn::White => StoneType::Black,
+    fn is_self_capture(&self, point_idx: i32) -> bool {
+        !self.board.has_liberties(point_idx)
+    }
+
+    // places the stone for real and takes it back again if it turns out illegal
+    fn try_place_stone(&mut self, i: i32) -> Result<(), IllegalMove> {
+        if self.board.points[i as usize].ty != StoneType::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+        self.board.place_stone(i, self.turn.stone());
+        let captured = self.update_captures(i);
+        let self_captured = if self.rules.allow_suicide {
+            self.update_self_captures(i)
+        } else {
+            vec![]
         };
-        let mut captured_idxs = vec![];
+        let record = MoveRecord {
+            mv: Move::Place(i),
+            player: self.turn,
+            captured,
+            self_captured,
+            hash: self.board.hash,
+        };
+        let legal = if record.self_captured.len() == 1
+            || (record.self_captured.is_empty() && self.is_self_capture(i))
+        {
+            Err(IllegalMove::Suicide)
+        } else {
+            self.board
+                .check_repetition(self.rules.ko_rule, self.board.hash, self.turn.other())
+        };
+        match legal {
+            Ok(()) => self.board.save_move(record),
+            Err(_) => self.board.unapply_move(&record),
+        }
+        legal
+    }
 
-        let start_point = &self.board.points[point_idx as usize];
-        'outer: for start_idx in start_point.neighbors.iter() {
-            // redundant but skips allocs if no potential to capture
-            if self.board.points[*start_idx as usize].ty != captured_type {
+    // same answer as play_point, worked out from the chains without placing the stone
+    pub fn is_legal(&self, idx: i32) -> Result<(), IllegalMove> {
+        let board = &self.board;
+        if self.phase() == GamePhase::Finished {
+            return Err(IllegalMove::GameOver);
+        }
+        if board.points[idx as usize].ty != StoneType::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+        if self.handicap_to_place > 0 {
+            return Ok(());
+        }
+        let player = self.turn.stone();
+        let mut hash = board.hash ^ board.zobrist.stone(idx, player);
+        let mut has_liberty = false;
+        let mut captured_heads = vec![];
+        let mut friendly_heads = vec![];
+        for &n in &board.points[idx as usize].neighbors {
+            let ty = board.points[n as usize].ty;
+            if ty == StoneType::Empty {
+                has_liberty = true;
                 continue;
             }
-            let mut search_stack = vec![*start_idx];
-            // TODO associative map?
-            let mut checked_idxs = vec![];
-
-            while let Some(i) = search_stack.pop() {
-                let point = &self.board.points[i as usize];
-                match point.ty {
-                    StoneType::Empty => continue 'outer,
-                    _ => {
-                        if point.ty == captured_type && checked_idxs.iter().all(|&x| x != i) {
-                            search_stack.extend(point.neighbors.iter());
-                            checked_idxs.push(i);
-                        }
-                    }
+            let head = board.chain_head(n);
+            let last_liberty = board.is_last_liberty(n, idx);
+            if ty == player {
+                has_liberty |= !last_liberty;
+                if !friendly_heads.contains(&head) {
+                    friendly_heads.push(head);
+                }
+            } else if last_liberty && !captured_heads.contains(&head) {
+                captured_heads.push(head);
+                for i in board.chain_stones(head) {
+                    hash ^= board.zobrist.stone(i, ty);
+                }
+            }
+        }
+        if captured_heads.is_empty() && !has_liberty {
+            // only suicide of more than one stone, where the rules allow it
+            if !self.rules.allow_suicide || friendly_heads.is_empty() {
+                return Err(IllegalMove::Suicide);
+            }
+            hash ^= board.zobrist.stone(idx, player);
+            for head in friendly_heads {
+                for i in board.chain_stones(head) {
+                    hash ^= board.zobrist.stone(i, player);
                 }
             }
-
-            // capture success if we make it here
-            captured_idxs.append(&mut checked_idxs);
         }
+        board.check_repetition(self.rules.ko_rule, hash, self.turn.other())
+    }
+
+    pub fn legal_moves(&self) -> impl Iterator<Item = i32> + '_ {
+        (0..self.board.points.len() as i32).filter(|&i| self.is_legal(i).is_ok())
+    }
+
+    // the point under the cursor, None between points
+    pub fn pick_point(&self, pos: SpinorT::Point) -> Option<i32> {
+        self.board.pick_point(pos)
+    }
 
-        for i in captured_idxs.iter() {
-            self.board.points[*i as usize].ty = StoneType::Empty;
-            // TODO scoring?
+    // a move for the player to move, straight from the point index
+    pub fn play(&mut self, i: i32) -> Result<(), IllegalMove> {
+        if self.phase() == GamePhase::Finished {
+            return Err(IllegalMove::GameOver);
+        }
+        if self.handicap_to_place > 0 {
+            return if self.place_handicap_stone(i) {
+                Ok(())
+            } else {
+                Err(IllegalMove::Occupied)
+            };
         }
+        self.try_place_stone(i)?;
+        self.turn = self.turn.other();
+        self.score = None;
+        self.illegal_move = None;
+        self.needs_render = true;
+        self.press_clock();
+        Ok(())
+    }
 
-        !captured_idxs.is_empty()
+    pub fn play_point(&mut self, i: i32) -> bool {
+        self.play(i).is_ok()
     }
 
-    fn is_self_capture(&self, point_idx: i32) -> bool {
-        let captured_type = match self.turn {
-            Turn::Black => StoneType::Black,
-            Turn::White => StoneType::White,
-        };
-        let mut search_stack = vec![point_idx];
-        let mut checked_idxs = vec![];
-
-        while let Some(i) = search_stack.pop() {
-            let point = &self.board.points[i as usize];
-            match point.ty {
-                StoneType::Empty => return false,
-                _ => {
-                    if point.ty == captured_type && checked_idxs.iter().all(|&x| x != i) {
-                        search_stack.extend(point.neighbors.iter());
-                        checked_idxs.push(i);
-                    }
-                }
+    pub fn play_move(&mut self, mv: Move) -> bool {
+        match mv {
+            Move::Place(i) => self.play_point(i),
+            Move::Pass => {
+                self.pass_move();
+                true
             }
         }
-        true
     }
 
-    fn try_select_point(&mut self, pos: SpinorT::Point) -> bool {
-        // TODO radius is wrong, should be dynamic here
-        let i = self.board.find_point(pos, STONE_RADIUS as f64);
-        if i >= 0 {
-            let point = &mut self.board.points[i as usize];
-            info!(
-                "found point {:?} {:?}, neighbors {:?}",
-                i, point.pos, point.neighbors
-            );
-            match point.ty {
-                StoneType::Empty => {
-                    match self.turn {
-                        Turn::Black => point.ty = StoneType::Black,
-                        Turn::White => point.ty = StoneType::White,
-                    };
-                    if !self.update_captures(i) {
-                        if self.is_self_capture(i) {
-                            info!("self capture");
-                            let point = &mut self.board.points[i as usize];
-                            point.ty = StoneType::Empty;
-                            return false;
-                        }
-                    }
-                    self.board.save_move();
-                    if self.board.last_move_is_ko() {
-                        info!("ko");
-                        self.board.move_history(-1);
-                        return false;
-                    }
-                    true
+    // a click on a point, plays there or marks stones dead while scoring
+    pub fn select_point(&mut self, i: i32) {
+        match self.phase() {
+            GamePhase::Playing => {
+                if let Err(reason) = self.play(i) {
+                    info!("illegal move at {:?}: {}", i, reason);
+                    self.illegal_move = Some(reason);
+                    self.needs_render = true;
                 }
-                _ => false,
             }
-        } else {
-            info!("no point found at {:?}", pos);
-            false
+            GamePhase::Scoring => self.toggle_dead(i),
+            GamePhase::Finished => {}
         }
     }
 
-    pub fn select_point(&mut self, pos: SpinorT::Point) {
-        if self.try_select_point(pos) {
-            self.turn = match self.turn {
-                Turn::Black => Turn::White,
-                Turn::White => Turn::Black,
-            };
-            self.score = None;
-            self.needs_render = true;
+    // a second pass in a row moves on to scoring
+    pub fn pass_move(&mut self) {
+        if self.phase() == GamePhase::Finished || self.handicap_to_place > 0 {
+            return;
+        }
+        let last_was_pass = self
+            .last_move()
+            .is_some_and(|record| record.mv == Move::Pass);
+        self.board.save_move(MoveRecord {
+            mv: Move::Pass,
+            player: self.turn,
+            captured: Vec::new(),
+            self_captured: Vec::new(),
+            hash: self.board.hash,
+        });
+        self.turn = self.turn.other();
+        self.illegal_move = None;
+        self.needs_render = true;
+        self.press_clock();
+        if last_was_pass && self.phase() == GamePhase::Playing {
+            self.calculate_score();
         }
     }
 
-    pub fn pass_move(&mut self) {
-        self.turn = match self.turn {
-            Turn::Black => Turn::White,
-            Turn::White => Turn::Black,
-        };
-        self.board.save_move();
+    pub fn resign(&mut self, player: Turn) {
+        self.finish(GameResult {
+            winner: Some(player.other()),
+            reason: ResultReason::Resignation,
+        });
+    }
+
+    // ends the game, later results don't override earlier ones
+    pub fn finish(&mut self, result: GameResult) {
+        if self.phase() == GamePhase::Finished {
+            return;
+        }
+        self.result = Some(result);
         self.needs_render = true;
     }
 
+    pub fn phase(&self) -> GamePhase {
+        if self.result.is_some() {
+            GamePhase::Finished
+        } else if self.score.is_some() {
+            GamePhase::Scoring
+        } else {
+            GamePhase::Playing
+        }
+    }
+
     pub fn check_hover_point(
         &mut self,
         maybe_pos: Option<SpinorT::Point>,
     ) -> Option<(SpinorT::Point, i32)> {
         if let Some(pos) = maybe_pos {
-            // TODO radius should be same as try_select_point
-            self.hover_idx = self.board.find_point(pos, STONE_RADIUS as f64);
+            self.hover_idx = self.board.pick_point(pos).unwrap_or(-1);
             if self.hover_idx >= 0 {
                 Some((
                     self.board.points[self.hover_idx as usize].pos,
@@ -404,31 +1343,219 @@ impl<SpinorT: Spinor> GameState<SpinorT> {
     }
 
     pub fn move_history(&mut self, offset: i32) {
-        let real_offset = self.board.move_history(offset);
-        if real_offset.abs() % 2 == 1 {
-            self.turn = match self.turn {
-                Turn::Black => Turn::White,
-                Turn::White => Turn::Black,
-            };
+        self.board.move_history(offset);
+        self.turn = self.board.next_turn();
+        self.score = None;
+        self.illegal_move = None;
+        self.needs_render = true;
+    }
+
+    pub fn go_to_parent(&mut self) {
+        self.move_history(-1);
+    }
+
+    pub fn go_to_child(&mut self) {
+        self.move_history(1);
+    }
+
+    // switch to a sibling variation of the current move
+    pub fn move_variation(&mut self, offset: i32) {
+        if self.board.move_variation(offset) {
+            self.turn = self.board.next_turn();
+            self.score = None;
+            self.illegal_move = None;
+            self.needs_render = true;
         }
+    }
+
+    pub fn go_to_mainline(&mut self) {
+        self.board.go_to_mainline();
+        self.turn = self.board.next_turn();
         self.score = None;
+        self.illegal_move = None;
         self.needs_render = true;
     }
 
+    pub fn board(&self) -> &Board<SpinorT> {
+        &self.board
+    }
+
+    pub fn tiling_parameters(&self) -> TilingParameters {
+        self.board.tiling_parameters
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.board.points.len()
+    }
+
+    pub fn is_square_grid(&self) -> bool {
+        self.board.is_square_grid()
+    }
+
+    pub fn grid_coords(&self, idx: i32) -> Option<(u32, u32)> {
+        self.board.grid_coords(idx)
+    }
+
+    pub fn point_at_grid(&self, col: u32, row: u32) -> Option<i32> {
+        self.board.point_at_grid(col, row)
+    }
+
+    pub fn point_name(&self, idx: i32) -> PointName {
+        self.board.point_name(idx)
+    }
+
+    pub fn point_by_name(&self, name: PointName) -> Option<i32> {
+        self.board.point_by_name(name)
+    }
+
+    pub fn turn(&self) -> Turn {
+        self.turn
+    }
+
+    // lets the same player move twice, as sgf and gtp allow
+    pub fn set_turn(&mut self, turn: Turn) {
+        self.turn = turn;
+    }
+
+    pub fn tree(&self) -> &GameTree {
+        &self.board.tree
+    }
+
+    // (index, count) of the current move among its sibling variations
+    pub fn get_variation(&self) -> (usize, usize) {
+        let tree = &self.board.tree;
+        let current = tree.current();
+        match tree.parent(current) {
+            Some(parent) => (tree.sibling_idx(current), tree.children(parent).len()),
+            None => (0, 1),
+        }
+    }
+
     pub fn get_turn_count(&self) -> i32 {
-        self.board.history_idx + 1
+        self.board.history_idx() + 1
+    }
+
+    // moves leading up to the current position
+    pub fn moves(&self) -> Vec<&MoveRecord> {
+        let tree = &self.board.tree;
+        tree.path_to(tree.current())
+            .into_iter()
+            .map(|node| tree.record(node).unwrap())
+            .collect()
+    }
+
+    // stones captured by player so far
+    pub fn prisoners(&self, player: Turn) -> u32 {
+        self.board.move_counts().0[player as usize]
+    }
+
+    pub fn last_move(&self) -> Option<&MoveRecord> {
+        self.board.tree.record(self.board.tree.current())
     }
 
-    // TODO consider dead stone removal
+    pub fn is_scoring(&self) -> bool {
+        self.phase() == GamePhase::Scoring
+    }
+
+    // enters or leaves scoring, dead stone marks are dropped on leaving
+    pub fn toggle_scoring(&mut self) {
+        match self.phase() {
+            GamePhase::Playing => self.calculate_score(),
+            GamePhase::Scoring => {
+                self.score = None;
+                self.needs_render = true;
+            }
+            GamePhase::Finished => {}
+        }
+    }
+
+    // switches rules along with their usual komi
+    pub fn set_scoring_rule(&mut self, rule: ScoringRule) {
+        self.rules.scoring_rule = rule;
+        self.komi = rule.default_komi();
+        if self.is_scoring() {
+            self.calculate_score();
+        }
+    }
+
+    // marks the whole chain through idx dead, or alive again
+    pub fn toggle_dead(&mut self, idx: i32) {
+        if self.phase() != GamePhase::Scoring {
+            return;
+        }
+        let Some(score) = &self.score else {
+            return;
+        };
+        if self.board.points[idx as usize].ty == StoneType::Empty {
+            return;
+        }
+        let mut dead = score.dead.clone();
+        let is_dead = !dead[idx as usize];
+        for i in self.board.chain_stones(idx) {
+            dead[i as usize] = is_dead;
+        }
+        self.score = Some(self.score_with_dead(dead));
+        self.needs_render = true;
+    }
+
+    // once both players confirm the marked stones the score becomes final
+    pub fn confirm_score(&mut self, player: Turn) {
+        if self.phase() != GamePhase::Scoring {
+            return;
+        }
+        let Some(score) = &mut self.score else {
+            return;
+        };
+        match player {
+            Turn::Black => score.black_confirmed = true,
+            Turn::White => score.white_confirmed = true,
+        }
+        if score.black_confirmed && score.white_confirmed {
+            let margin = score.margin();
+            self.final_score = Some(FinalScore {
+                rule: score.rule,
+                black: score.black,
+                white: score.white,
+                dead: score.dead_stones(),
+            });
+            self.finish(GameResult {
+                winner: match margin {
+                    m if m > 0.0 => Some(Turn::Black),
+                    m if m < 0.0 => Some(Turn::White),
+                    _ => None,
+                },
+                reason: ResultReason::Score(margin.abs()),
+            });
+        }
+        self.needs_render = true;
+    }
+
+    // rescores the board, keeping any dead stone marks
     pub fn calculate_score(&mut self) {
-        let mut territory = self.board.history[self.board.history_idx as usize].clone();
+        let dead = match self.score.take() {
+            Some(score) => score.dead,
+            None => vec![false; self.board.points.len()],
+        };
+        self.score = Some(self.score_with_dead(dead));
+        self.needs_render = true;
+    }
+
+    fn score_with_dead(&self, dead: Vec<bool>) -> ScoreState {
+        let is_open = |i: usize| self.board.points[i].ty == StoneType::Empty || dead[i];
+        let mut territory: Vec<StoneType> = (0..self.board.points.len())
+            .map(|i| {
+                if is_open(i) {
+                    StoneType::Empty
+                } else {
+                    self.board.points[i].ty
+                }
+            })
+            .collect();
 
         let mut checked = vec![false; self.board.points.len()];
 
         for start_idx in 0..self.board.points.len() as i32 {
-            if checked[start_idx as usize]
-                || self.board.points[start_idx as usize].ty != StoneType::Empty
-            {
+            if checked[start_idx as usize] || !is_open(start_idx as usize) {
                 continue;
             }
 
@@ -439,15 +1566,17 @@ impl<SpinorT: Spinor> GameState<SpinorT> {
 
             while let Some(i) = search_stack.pop() {
                 let point = &self.board.points[i as usize];
-                match point.ty {
-                    StoneType::Empty => {
-                        if checked[i as usize] {
-                            continue;
-                        }
-                        checked[i as usize] = true;
-                        search_stack.extend(point.neighbors.iter());
-                        region_idxs.push(i);
+                if is_open(i as usize) {
+                    if checked[i as usize] {
+                        continue;
                     }
+                    checked[i as usize] = true;
+                    search_stack.extend(point.neighbors.iter());
+                    region_idxs.push(i);
+                    continue;
+                }
+                match point.ty {
+                    StoneType::Empty => {}
                     StoneType::Black => {
                         borders_black = true;
                     }
@@ -471,25 +1600,427 @@ impl<SpinorT: Spinor> GameState<SpinorT> {
             }
         }
 
-        let mut black_score = 0;
-        let mut white_score = 0;
-        for ty in &territory {
-            match ty {
-                StoneType::Empty => {}
-                StoneType::Black => {
-                    black_score += 1;
-                }
-                StoneType::White => {
-                    white_score += 1;
-                }
+        let (prisoners, passes) = self.board.move_counts();
+        let mut scores = [PlayerScore::default(); 2];
+        for (i, &ty) in territory.iter().enumerate() {
+            let point_ty = self.board.points[i].ty;
+            if dead[i] {
+                scores[Turn::from_stone(point_ty).other() as usize].prisoners += 1;
+            }
+            if ty == StoneType::Empty {
+                continue;
+            }
+            let owner = &mut scores[Turn::from_stone(ty) as usize];
+            if point_ty == ty && !dead[i] {
+                owner.stones += 1;
+            } else {
+                owner.territory += 1;
+            }
+        }
+        for player in [Turn::Black, Turn::White] {
+            let score = &mut scores[player as usize];
+            score.prisoners += prisoners[player as usize] as i32;
+            if self.rules.scoring_rule == ScoringRule::Aga {
+                score.prisoners += passes[player.other() as usize] as i32;
+            }
+            if player == Turn::White {
+                score.komi = self.komi;
             }
+            let points = match self.rules.scoring_rule {
+                ScoringRule::Area => score.stones + score.territory,
+                ScoringRule::Territory | ScoringRule::Aga => score.territory + score.prisoners,
+            };
+            score.total = points as f64 + score.komi;
         }
 
-        self.score = Some(ScoreState {
+        ScoreState {
             territory,
-            black_score,
-            white_score,
-        });
-        self.needs_render = true;
+            dead,
+            rule: self.rules.scoring_rule,
+            black: scores[0],
+            white: scores[1],
+            black_confirmed: false,
+            white_confirmed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+
+    fn make_test_board() -> Board<SpinorEuclidian> {
+        Board::make_board(TilingParameters::new::<SpinorEuclidian>(5, 4, 4))
+    }
+
+    fn play(board: &mut Board<SpinorEuclidian>, mv: Move, player: Turn, captured: Vec<i32>) {
+        let mut record = MoveRecord {
+            mv,
+            player,
+            captured,
+            self_captured: vec![],
+            hash: 0,
+        };
+        board.apply_move(&record);
+        record.hash = board.hash;
+        board.save_move(record);
+    }
+
+    #[test]
+    fn test_superko() {
+        let mut board = make_test_board();
+
+        play(&mut board, Move::Place(0), Turn::Black, vec![]);
+        let repeated_hash = board.hash;
+        play(&mut board, Move::Place(1), Turn::White, vec![]);
+        play(&mut board, Move::Place(2), Turn::Black, vec![1]);
+
+        // back to the first position, but not the one just before the last move
+        board.set_stone(2, StoneType::Empty);
+        assert_eq!(board.hash, repeated_hash);
+        assert!(!board.repeats_hash(KoRule::Simple, board.hash, Turn::White));
+        assert!(board.repeats_hash(KoRule::PositionalSuperko, board.hash, Turn::Black));
+        assert!(board.repeats_hash(KoRule::SituationalSuperko, board.hash, Turn::White));
+        assert!(!board.repeats_hash(KoRule::SituationalSuperko, board.hash, Turn::Black));
+        let superko = board.check_repetition(KoRule::PositionalSuperko, board.hash, Turn::White);
+        assert_eq!(superko, Err(IllegalMove::Superko));
+        let simple = board.check_repetition(KoRule::Simple, board.hash, Turn::White);
+        assert_eq!(simple, Ok(()));
+
+        // the position before white's stone, taken by black
+        let ko_hash = board.hash ^ board.zobrist.stone(1, StoneType::White);
+        let ko = board.check_repetition(KoRule::PositionalSuperko, ko_hash, Turn::White);
+        assert_eq!(ko, Err(IllegalMove::Ko));
+    }
+
+    #[test]
+    fn test_move_history() {
+        let mut board = make_test_board();
+
+        play(&mut board, Move::Place(0), Turn::Black, vec![]);
+        play(&mut board, Move::Place(1), Turn::White, vec![]);
+        play(&mut board, Move::Pass, Turn::Black, vec![]);
+        play(&mut board, Move::Place(2), Turn::White, vec![0]);
+        let final_hash = board.hash;
+
+        assert_eq!(board.move_history(-5), 0);
+        assert_eq!(board.move_history(-4), -4);
+        assert!(board.points.iter().all(|p| p.ty == StoneType::Empty));
+        assert_eq!(board.hash, 0);
+        assert_eq!(board.next_turn(), Turn::Black);
+
+        assert_eq!(board.move_history(4), 4);
+        assert_eq!(board.points[0].ty, StoneType::Empty);
+        assert_eq!(board.points[1].ty, StoneType::White);
+        assert_eq!(board.points[2].ty, StoneType::White);
+        assert_eq!(board.hash, final_hash);
+        assert_eq!(board.next_turn(), Turn::Black);
+    }
+
+    #[test]
+    fn test_variations() {
+        let mut board = make_test_board();
+
+        play(&mut board, Move::Place(0), Turn::Black, vec![]);
+        play(&mut board, Move::Place(1), Turn::White, vec![]);
+        assert_eq!(board.move_history(-1), -1);
+        play(&mut board, Move::Place(2), Turn::White, vec![]);
+        assert_eq!(board.points[1].ty, StoneType::Empty);
+        assert_eq!(board.points[2].ty, StoneType::White);
+        assert!(!board.tree.is_mainline(board.tree.current()));
+
+        assert!(board.move_variation(-1));
+        assert_eq!(board.points[1].ty, StoneType::White);
+        assert_eq!(board.points[2].ty, StoneType::Empty);
+        assert!(!board.move_variation(-1));
+
+        assert!(board.move_variation(1));
+        board.go_to_mainline();
+        assert_eq!(board.history_idx(), 1);
+        assert_eq!(board.points[0].ty, StoneType::Black);
+        assert_eq!(board.points[2].ty, StoneType::Empty);
+
+        // stepping forward follows the last visited variation
+        assert_eq!(board.move_history(1), 1);
+        assert_eq!(board.points[2].ty, StoneType::White);
+    }
+
+    #[test]
+    fn test_variation_other_color() {
+        let mut game =
+            GameState::<SpinorEuclidian>::new(TilingParameters::new::<SpinorEuclidian>(5, 4, 4), 0);
+        assert!(game.play_point(12));
+        game.move_history(-1);
+        game.set_turn(Turn::White);
+        assert!(game.play_point(12));
+        assert_eq!(game.board.stone(12), StoneType::White);
+        assert_eq!(game.last_move().unwrap().player, Turn::White);
+        assert_eq!(game.board.tree.children(ROOT_NODE).len(), 2);
+        assert_eq!(game.turn(), Turn::Black);
+
+        game.select_point(12);
+        assert_eq!(game.illegal_move, Some(IllegalMove::Occupied));
+        game.move_variation(-1);
+        assert_eq!(game.board.stone(12), StoneType::Black);
+        assert_eq!(game.illegal_move, None);
+
+        let sgf = "(;SZ[5](;B[aa])(;W[aa]))";
+        let game = GameState::<SpinorEuclidian>::from_sgf(sgf).unwrap();
+        assert!(game.to_sgf().ends_with("SZ[5](;B[aa])(;W[aa]))\n"));
+    }
+
+    #[test]
+    fn test_platonic_boards() {
+        use crate::geometry::spherical::SpinorSpherical;
+
+        // (sides, around_vertex, vertex count)
+        for (sides, around_vertex, count) in
+            [(3, 3, 4), (4, 3, 8), (3, 4, 6), (5, 3, 20), (3, 5, 12)]
+        {
+            let params = TilingParameters::new::<SpinorSpherical>(11, sides, around_vertex);
+            let board = Board::<SpinorSpherical>::make_board(params);
+            assert_eq!(board.point_count(), count);
+            for i in 0..count as i32 {
+                assert_eq!(board.neighbors(i).len(), around_vertex as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn test_board_boundaries() {
+        use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+        let board =
+            Board::<SpinorEuclidian>::make_board(TilingParameters::new::<SpinorEuclidian>(4, 4, 4));
+        assert_eq!(board.point_count(), 16);
+        assert!(board.is_square_grid());
+        for i in 0..16 {
+            let (col, row) = board.grid_coords(i).unwrap();
+            assert!(col < 4 && row < 4);
+            assert_eq!(board.point_at_grid(col, row), Some(i));
+        }
+
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let board =
+            Board::<SpinorEuclidian>::make_board(params.with_boundary(Boundary::Radius(1.0)));
+        assert_eq!(board.point_count(), 5);
+        assert!(!board.is_square_grid());
+
+        // (center, boundary, point count)
+        let cases = [
+            (BoardCenter::Vertex, Boundary::GraphDistance(1), 5),
+            (BoardCenter::Edge, Boundary::GraphDistance(0), 2),
+            (BoardCenter::Face, Boundary::GraphDistance(0), 5),
+            (BoardCenter::Face, Boundary::GraphDistance(1), 15),
+            (BoardCenter::Vertex, Boundary::PointCount(81), 81),
+            (BoardCenter::Face, Boundary::PointCount(361), 361),
+        ];
+        for (center, boundary, count) in cases {
+            let params = TilingParameters::new::<SpinorHyperbolic>(1, 5, 4)
+                .with_center(center)
+                .with_boundary(boundary);
+            let board = Board::<SpinorHyperbolic>::make_board(params);
+            assert_eq!(board.point_count(), count);
+            for (a, b) in board.links() {
+                assert!(board.neighbors(*a).contains(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pick_point() {
+        use crate::geometry::hyperbolic::{PointHyperbolic, SpinorHyperbolic};
+        use cgmath::assert_abs_diff_eq;
+
+        // stones are big enough to touch on {7, 3}, there is a gap between them on {5, 4}
+        for (sides, around_vertex, touching) in [(7, 3, true), (5, 4, false)] {
+            let params = TilingParameters::new::<SpinorHyperbolic>(3, sides, around_vertex);
+            let board = Board::<SpinorHyperbolic>::make_board(params);
+            let radius = board.pick_radius();
+            assert_eq!(radius == 0.5 * params.distance, touching);
+
+            // dist away from point i, towards its neighbor n
+            let towards = |i: i32, n: i32, dist: f64| {
+                let transform = board.points[i as usize].transform;
+                let angle = transform.reverse().apply(board.point_pos(n)).angle();
+                let offset = SpinorHyperbolic::translation(dist, angle);
+                transform.apply(offset.apply(PointHyperbolic::zero()))
+            };
+            for i in 0..board.point_count() as i32 {
+                assert_eq!(board.pick_point(board.point_pos(i)), Some(i));
+                for &n in board.neighbors(i) {
+                    let pos = towards(i, n, 0.9 * radius);
+                    assert_abs_diff_eq!(
+                        pos.distance(board.point_pos(i)),
+                        0.9 * radius,
+                        epsilon = 1e-9
+                    );
+                    assert_eq!(board.pick_point(pos), Some(i));
+                }
+            }
+            if !touching {
+                let n = board.neighbors(0)[0];
+                let gap = 0.5 * (radius + 0.5 * params.distance);
+                assert_eq!(board.pick_point(towards(0, n, gap)), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dead_stones() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        game.komi = 0.0;
+        // white wall down the middle column, lone black stone on the right
+        let wall: Vec<i32> = (0..5)
+            .map(|row| game.point_at_grid(2, row).unwrap())
+            .collect();
+        let invader = game.point_at_grid(4, 4).unwrap();
+        for &idx in &wall {
+            game.set_turn(Turn::White);
+            assert!(game.play_point(idx));
+        }
+        assert!(game.play_point(invader));
+
+        game.toggle_scoring();
+        let score = game.score.as_ref().unwrap();
+        assert_eq!((score.black.total, score.white.total), (1.0, 15.0));
+
+        game.toggle_dead(invader);
+        let score = game.score.as_ref().unwrap();
+        assert!(score.is_dead(invader));
+        assert_eq!((score.black.total, score.white.total), (0.0, 25.0));
+        let (black, white) = (score.black, score.white);
+
+        let turn = game.turn();
+        assert_eq!(score.next_to_confirm(turn), turn);
+        game.confirm_score(turn);
+        assert!(game.final_score.is_none());
+        let score = game.score.as_ref().unwrap();
+        assert_eq!(score.next_to_confirm(turn), turn.other());
+        game.confirm_score(turn.other());
+        assert_eq!(game.turn(), turn);
+        assert_eq!(
+            game.final_score,
+            Some(FinalScore {
+                rule: ScoringRule::Area,
+                black,
+                white,
+                dead: vec![invader],
+            })
+        );
+    }
+
+    #[test]
+    fn test_scoring_rules() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        let mut play = |player: Turn, col: u32, row: u32| {
+            let idx = game.point_at_grid(col, row).unwrap();
+            game.set_turn(player);
+            assert!(game.play_point(idx));
+        };
+        // walls on columns 1 and 3, black captures a white stone in the corner
+        for row in 0..5 {
+            play(Turn::Black, 1, row);
+            play(Turn::White, 3, row);
+        }
+        play(Turn::White, 0, 0);
+        play(Turn::Black, 0, 1);
+        game.set_turn(Turn::White);
+        game.pass_move();
+        game.pass_move();
+
+        // (rule, black, white)
+        let cases = [
+            (ScoringRule::Area, 10.0, 10.5),
+            (ScoringRule::Territory, 5.0, 5.5),
+            (ScoringRule::Aga, 6.0, 6.5),
+        ];
+        for (rule, black, white) in cases {
+            game.rules.scoring_rule = rule;
+            game.komi = 0.5;
+            game.calculate_score();
+            let score = game.score.as_ref().unwrap();
+            assert_eq!(score.black.total, black);
+            assert_eq!(score.white.total, white);
+            assert_eq!(score.margin(), -0.5);
+        }
+    }
+
+    #[test]
+    fn test_game_phases() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        assert!(game.play_point(0));
+        game.pass_move();
+        assert_eq!(game.phase(), GamePhase::Playing);
+        game.pass_move();
+        assert_eq!(game.phase(), GamePhase::Scoring);
+
+        // playing on resumes the game
+        assert!(game.play_point(1));
+        assert_eq!(game.phase(), GamePhase::Playing);
+        game.pass_move();
+        game.pass_move();
+        game.confirm_score(Turn::Black);
+        game.confirm_score(Turn::White);
+        assert_eq!(game.phase(), GamePhase::Finished);
+        let result = game.result.unwrap();
+        assert_eq!(result.winner, Some(Turn::White));
+        assert_eq!(result.to_string(), "W+7.5");
+
+        assert_eq!(game.play(2), Err(IllegalMove::GameOver));
+        game.pass_move();
+        game.resign(Turn::White);
+        assert_eq!(game.result, Some(result));
+        assert_eq!(game.get_turn_count(), 7);
+
+        for notation in ["B+R", "W+T", "B+0.5", "0"] {
+            assert_eq!(
+                notation.parse::<GameResult>().unwrap().to_string(),
+                notation
+            );
+        }
+        assert!("W+F".parse::<GameResult>().is_err());
+    }
+
+    #[test]
+    fn test_suicide() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        for rules in [Rules::chinese(), Rules::new_zealand()] {
+            let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+            game.rules = rules;
+            let mut play = |player: Turn, col: u32, row: u32| {
+                let idx = game.point_at_grid(col, row).unwrap();
+                game.set_turn(player);
+                game.play_point(idx)
+            };
+            for (col, row) in [(2, 0), (0, 1), (1, 1)] {
+                assert!(play(Turn::White, col, row));
+            }
+            assert!(play(Turn::Black, 0, 0));
+            assert_eq!(play(Turn::Black, 1, 0), rules.allow_suicide);
+
+            if rules.allow_suicide {
+                let record = game.last_move().unwrap();
+                assert_eq!(record.self_captured.len(), 2);
+                assert_eq!(game.prisoners(Turn::White), 2);
+                let corner = game.point_at_grid(0, 0).unwrap();
+                assert_eq!(game.board().stone(corner), StoneType::Empty);
+                game.go_to_parent();
+                assert_eq!(game.board().stone(corner), StoneType::Black);
+            }
+
+            // on its own the stone would just disappear again
+            let corner = game.point_at_grid(4, 4).unwrap();
+            game.set_turn(Turn::White);
+            assert!(game.play_point(game.point_at_grid(3, 4).unwrap()));
+            assert!(game.play_point(game.point_at_grid(0, 4).unwrap()));
+            game.set_turn(Turn::White);
+            assert!(game.play_point(game.point_at_grid(4, 3).unwrap()));
+            assert_eq!(game.play(corner), Err(IllegalMove::Suicide));
+        }
     }
 }
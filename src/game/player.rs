@@ -0,0 +1,219 @@
+use super::search::*;
+use super::*;
+
+const CAPTURE_VALUE: i32 = 10;
+const SAVE_VALUE: i32 = 8;
+const SELF_ATARI_VALUE: i32 = -5;
+
+// who picks the moves for one color
+#[derive(Copy, Clone, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum Player {
+    #[default]
+    Human,
+    // uniform over the legal moves, passes once there are none
+    Random,
+    // captures, saves groups in atari and keeps its own eyes
+    Heuristic,
+    // monte carlo tree search
+    Mcts,
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Player::Human => "human",
+            Player::Random => "random",
+            Player::Heuristic => "heuristic",
+            Player::Mcts => "mcts",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Player {
+    pub fn is_bot(self) -> bool {
+        self != Player::Human
+    }
+
+    // None for humans, search is only used by the mcts bot
+    pub fn choose_move<SpinorT: Spinor>(
+        self,
+        game: &GameState<SpinorT>,
+        search_params: &SearchParams,
+        rng_state: &mut u64,
+    ) -> Option<Move> {
+        match self {
+            Player::Human => None,
+            Player::Random => {
+                let moves: Vec<i32> = game.legal_moves().collect();
+                if moves.is_empty() {
+                    return Some(Move::Pass);
+                }
+                let k = next_key(rng_state) % moves.len() as u64;
+                Some(Move::Place(moves[k as usize]))
+            }
+            // the tree assumes alternating moves, handicap stones come first
+            Player::Heuristic | Player::Mcts if game.handicap_to_place() > 0 => {
+                Some(heuristic_move(game, rng_state))
+            }
+            Player::Heuristic => Some(heuristic_move(game, rng_state)),
+            Player::Mcts => Some(search(game, search_params, next_key(rng_state)).best),
+        }
+    }
+
+    // the tree search detached from the game, to be run off the caller's thread
+    pub fn search_job<SpinorT: Spinor>(
+        self,
+        game: &GameState<SpinorT>,
+        search_params: &SearchParams,
+        rng_state: &mut u64,
+    ) -> Option<impl FnOnce() -> Move + Send + 'static> {
+        if self != Player::Mcts || game.handicap_to_place() > 0 {
+            return None;
+        }
+        let root = SearchRoot::new(game);
+        let params = *search_params;
+        let seed = next_key(rng_state);
+        Some(move || root.search(&params, seed).best)
+    }
+}
+
+// every neighbor is already one of the player's stones
+fn is_own_eye<SpinorT: Spinor>(board: &Board<SpinorT>, idx: i32, player: StoneType) -> bool {
+    board
+        .neighbors(idx)
+        .iter()
+        .all(|&n| board.stone(n) == player)
+}
+
+// stones captured and saved from atari by playing idx, less for putting itself
+// in atari
+fn heuristic_value<SpinorT: Spinor>(board: &Board<SpinorT>, idx: i32, player: StoneType) -> i32 {
+    let mut heads = vec![];
+    let mut liberties = vec![];
+    let (mut captured, mut saved, mut group_size) = (0, 0, 1);
+    for &n in board.neighbors(idx) {
+        let ty = board.stone(n);
+        if ty == StoneType::Empty {
+            liberties.push(n);
+            continue;
+        }
+        let head = board.chain_head(n);
+        if heads.contains(&head) {
+            continue;
+        }
+        heads.push(head);
+        let size = board.chain_size(n) as i32;
+        let in_atari = board.is_last_liberty(n, idx);
+        if ty == player {
+            group_size += size;
+            if in_atari {
+                saved += size;
+            }
+            liberties.extend(board.liberties(n));
+        } else if in_atari {
+            captured += size;
+        }
+    }
+    liberties.sort_unstable();
+    liberties.dedup();
+    liberties.retain(|&i| i != idx);
+    // a capture always opens up at least one liberty
+    let liberty_count = liberties.len() + (captured > 0) as usize;
+
+    let mut value = CAPTURE_VALUE * captured;
+    if liberty_count >= 2 {
+        value += SAVE_VALUE * saved;
+    } else {
+        value += SELF_ATARI_VALUE * group_size;
+    }
+    value
+}
+
+// best valued move with random tie breaks, passes when nothing is worth playing
+fn heuristic_move<SpinorT: Spinor>(game: &GameState<SpinorT>, rng_state: &mut u64) -> Move {
+    let board = game.board();
+    let player = game.turn().stone();
+    let mut best = Move::Pass;
+    let mut best_value = 0;
+    let mut ties = 0;
+    for idx in game.legal_moves() {
+        if is_own_eye(board, idx, player) {
+            continue;
+        }
+        let value = heuristic_value(board, idx, player);
+        if value > best_value || (best == Move::Pass && value == best_value) {
+            best = Move::Place(idx);
+            best_value = value;
+            ties = 1;
+        } else if value == best_value {
+            ties += 1;
+            if next_key(rng_state).is_multiple_of(ties) {
+                best = Move::Place(idx);
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    #[test]
+    fn test_legal_moves() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        for rules in [Rules::chinese(), Rules::new_zealand()] {
+            let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+            game.rules = rules;
+            let mut state = 7;
+            for _ in 0..400 {
+                let idx = (next_key(&mut state) % game.point_count() as u64) as i32;
+                let legal = game.is_legal(idx);
+                assert_eq!(game.play(idx), legal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_heuristic_bot() {
+        let params = TilingParameters::new::<SpinorEuclidian>(7, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        // a white stone down to its last liberty, away from two other white stones
+        let board = game.board();
+        let inner: Vec<i32> = (0..board.point_count() as i32)
+            .filter(|&i| board.neighbors(i).len() == 4)
+            .collect();
+        let white = inner[0];
+        let surround = board.neighbors(white).to_vec();
+        let far: Vec<i32> = inner
+            .iter()
+            .copied()
+            .filter(|&i| board.point_pos(i).distance(board.point_pos(white)) > 2.5)
+            .take(2)
+            .collect();
+        for (b, w) in surround[..3].iter().zip([white, far[0], far[1]]) {
+            assert!(game.play_point(*b));
+            assert!(game.play_point(w));
+        }
+        let mut state = 1;
+        let mv = Player::Heuristic.choose_move(&game, &SearchParams::default(), &mut state);
+        assert_eq!(mv, Some(Move::Place(surround[3])));
+
+        // the heuristic bot doesn't fill its eyes, so games between them end
+        let params = TilingParameters::new::<SpinorHyperbolic>(5, 5, 4);
+        let mut game = GameState::<SpinorHyperbolic>::new(params, 0);
+        for _ in 0..1000 {
+            if game.phase() != GamePhase::Playing {
+                break;
+            }
+            let mv = Player::Heuristic
+                .choose_move(&game, &SearchParams::default(), &mut state)
+                .unwrap();
+            assert!(game.play_move(mv));
+        }
+        assert_eq!(game.phase(), GamePhase::Scoring);
+    }
+}
@@ -62,9 +62,11 @@ impl Instance {
     }
 }
 
-fn make_link_verts<SpinorT: Spinor>(link_len: f64) -> Vec<SpinorT::Point> {
+fn make_link_verts<SpinorT: Spinor>(distance: f64) -> Vec<SpinorT::Point> {
     const LINK_WIDTH: f64 = 0.025;
-    let t = SpinorT::translation_to(SpinorT::Point::from_flat(link_len, 0.0));
+    // along +x, by distance rather than flat length so links longer than a
+    // quarter turn still work on the sphere
+    let t = SpinorT::translation(distance, -0.5 * PI);
 
     let b1 = SpinorT::Point::from_flat(-LINK_WIDTH / 2.0, -LINK_WIDTH / 2.0);
     let b2 = SpinorT::Point::from_flat(-LINK_WIDTH / 2.0, LINK_WIDTH / 2.0);
@@ -80,7 +82,7 @@ fn make_link_verts<SpinorT: Spinor>(link_len: f64) -> Vec<SpinorT::Point> {
     vec![b1, b2, b3, b4]
 }
 
-pub fn make_models<SpinorT: Spinor>(link_len: f64) -> Vec<Model> {
+pub fn make_models<SpinorT: Spinor>(distance: f64) -> Vec<Model> {
     iter::once((
         STONE_VERTS
             .iter()
@@ -89,7 +91,7 @@ pub fn make_models<SpinorT: Spinor>(link_len: f64) -> Vec<Model> {
         &STONE_INDICES,
     ))
     .chain(iter::once((
-        make_link_verts::<SpinorT>(link_len),
+        make_link_verts::<SpinorT>(distance),
         &LINK_INDICES,
     )))
     .map(|t| Model {
@@ -139,45 +141,82 @@ impl<SpinorT: Spinor> GameState<SpinorT> {
 
         let test_trans = SpinorT::translation(TEST_TRANS, 0.0);
 
-        for point in self.board.points.iter() {
+        for (i, point) in self.board.points.iter().enumerate() {
             if point.ty == StoneType::Empty {
                 continue;
             }
 
+            let mut color = match point.ty {
+                StoneType::Empty => [0.0, 0.2, 0.0, 0.2],
+                StoneType::Black => [0.0, 0.0, 0.0, 1.0],
+                StoneType::White => [1.0, 1.0, 1.0, 1.0],
+            };
+            // dead stones fade out so the territory marker shows through
+            if self
+                .score
+                .as_ref()
+                .is_some_and(|score| score.is_dead(i as i32))
+            {
+                color[3] = 0.4;
+            }
             instances.push(Instance {
                 transform: ((test_trans * point.relative_transform).into_mat4() * scale_mat).into(),
-                color: match point.ty {
-                    StoneType::Empty => [0.0, 0.2, 0.0, 0.2],
-                    StoneType::Black => [0.0, 0.0, 0.0, 1.0],
-                    StoneType::White => [1.0, 1.0, 1.0, 1.0],
-                },
+                color,
             });
             /*             if point.pos.distance(SpinorT::Point::zero()) > 10.1 {
                 info!("transform {:?}", instances.last().unwrap().transform);
             } */
         }
 
+        // while scoring the markers follow the dead stone marks, so they match the score
         if let Some(score) = &self.score {
             let territory_scale_mat =
                 Matrix4::from_scale(0.3 * self.board.tiling_parameters.stone_scale as f32);
 
             for (i, point) in self.board.points.iter().enumerate() {
-                if point.ty == StoneType::Empty {
-                    if score.territory[i] == StoneType::Empty {
-                        continue;
-                    }
-
-                    instances.push(Instance {
-                        transform: ((test_trans * point.relative_transform).into_mat4()
-                            * territory_scale_mat)
-                            .into(),
-                        color: match score.territory[i] {
-                            StoneType::Empty => [0.0, 0.2, 0.0, 0.2],
-                            StoneType::Black => [0.0, 0.0, 0.0, 0.5],
-                            StoneType::White => [0.35, 0.35, 0.35, 0.4],
-                        },
-                    });
+                if point.ty != StoneType::Empty && !score.is_dead(i as i32) {
+                    continue;
+                }
+                let color = match score.territory[i] {
+                    StoneType::Empty => continue,
+                    StoneType::Black => [0.0, 0.0, 0.0, 0.5],
+                    StoneType::White => [0.35, 0.35, 0.35, 0.4],
+                };
+                instances.push(Instance {
+                    transform: ((test_trans * point.relative_transform).into_mat4()
+                        * territory_scale_mat)
+                        .into(),
+                    color,
+                });
+            }
+        } else if let Some(ownership) = &self.ownership {
+            // graded by how sure the estimate is, on empty points and stones that look dead
+            const MIN_OWNERSHIP: f64 = 0.1;
+            const OWNERSHIP_SCALE: f64 = 0.4;
+
+            for (i, point) in self.board.points.iter().enumerate() {
+                let value = ownership.points[i];
+                let owner = if value > 0.0 {
+                    StoneType::Black
+                } else {
+                    StoneType::White
+                };
+                if value.abs() < MIN_OWNERSHIP || point.ty == owner {
+                    continue;
                 }
+                let ownership_scale_mat = Matrix4::from_scale(
+                    (OWNERSHIP_SCALE * value.abs() * self.board.tiling_parameters.stone_scale)
+                        as f32,
+                );
+                instances.push(Instance {
+                    transform: ((test_trans * point.relative_transform).into_mat4()
+                        * ownership_scale_mat)
+                        .into(),
+                    color: match owner {
+                        StoneType::Black => [0.0, 0.0, 0.0, 0.5],
+                        _ => [0.35, 0.35, 0.35, 0.4],
+                    },
+                });
             }
         }
 
@@ -0,0 +1,759 @@
+use std::sync::Arc;
+
+use super::*;
+
+// c in the uct formula
+const DEFAULT_EXPLORATION: f64 = 1.0;
+// playouts that don't end in two passes are scored after this many moves per point
+const PLAYOUT_MOVES_PER_POINT: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchParams {
+    pub playouts: u32,
+    // every thread grows its own tree, their root visits are added up
+    pub threads: u32,
+    pub exploration: f64,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            playouts: 1000,
+            threads: 1,
+            exploration: DEFAULT_EXPLORATION,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best: Move,
+    // (move, visits, win rate for the player to move), most visited first
+    pub moves: Vec<(Move, u32, f64)>,
+}
+
+impl SearchResult {
+    pub fn win_rate(&self) -> f64 {
+        self.moves.first().map_or(0.5, |&(_, _, rate)| rate)
+    }
+}
+
+// neighbor lists in one flat array, shared by every copy of a playout board
+struct Graph {
+    offsets: Vec<usize>,
+    neighbors: Vec<i32>,
+}
+
+impl Graph {
+    fn range(&self, idx: i32) -> std::ops::Range<usize> {
+        self.offsets[idx as usize]..self.offsets[idx as usize + 1]
+    }
+
+    fn neighbors(&self, idx: i32) -> &[i32] {
+        &self.neighbors[self.range(idx)]
+    }
+}
+
+// Just the stones and chains of a board, chains kept the same way as on Board.
+// Copies share the graph, and clone_from reuses the buffers, so playouts on a
+// scratch copy don't allocate. Only simple ko is checked and suicide is never
+// played.
+pub struct PlayoutBoard {
+    graph: Arc<Graph>,
+    stones: Vec<StoneType>,
+    chain: Vec<i32>,
+    next_stone: Vec<i32>,
+    // only on chain heads
+    chain_size: Vec<u32>,
+    pseudo_liberties: Vec<u32>,
+    // empty points, with each point's position in the list
+    empty: Vec<i32>,
+    empty_pos: Vec<usize>,
+    ko: i32,
+    to_move: Turn,
+    passes: u32,
+    komi: f64,
+}
+
+impl Clone for PlayoutBoard {
+    fn clone(&self) -> Self {
+        Self {
+            graph: Arc::clone(&self.graph),
+            stones: self.stones.clone(),
+            chain: self.chain.clone(),
+            next_stone: self.next_stone.clone(),
+            chain_size: self.chain_size.clone(),
+            pseudo_liberties: self.pseudo_liberties.clone(),
+            empty: self.empty.clone(),
+            empty_pos: self.empty_pos.clone(),
+            ko: self.ko,
+            to_move: self.to_move,
+            passes: self.passes,
+            komi: self.komi,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        if !Arc::ptr_eq(&self.graph, &source.graph) {
+            self.graph = Arc::clone(&source.graph);
+        }
+        self.stones.clone_from(&source.stones);
+        self.chain.clone_from(&source.chain);
+        self.next_stone.clone_from(&source.next_stone);
+        self.chain_size.clone_from(&source.chain_size);
+        self.pseudo_liberties.clone_from(&source.pseudo_liberties);
+        self.empty.clone_from(&source.empty);
+        self.empty_pos.clone_from(&source.empty_pos);
+        self.ko = source.ko;
+        self.to_move = source.to_move;
+        self.passes = source.passes;
+        self.komi = source.komi;
+    }
+}
+
+impl PlayoutBoard {
+    pub fn from_game<SpinorT: Spinor>(game: &GameState<SpinorT>) -> Self {
+        let board = game.board();
+        let n = board.point_count();
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut neighbors = vec![];
+        offsets.push(0);
+        for i in 0..n as i32 {
+            neighbors.extend_from_slice(board.neighbors(i));
+            offsets.push(neighbors.len());
+        }
+        let mut playout = Self {
+            graph: Arc::new(Graph { offsets, neighbors }),
+            stones: vec![StoneType::Empty; n],
+            chain: vec![-1; n],
+            next_stone: vec![-1; n],
+            chain_size: vec![0; n],
+            pseudo_liberties: vec![0; n],
+            empty: (0..n as i32).collect(),
+            empty_pos: (0..n).collect(),
+            ko: -1,
+            to_move: game.turn(),
+            passes: 0,
+            komi: game.komi,
+        };
+        for i in 0..n as i32 {
+            let ty = board.stone(i);
+            if ty != StoneType::Empty {
+                playout.place_stone(i, ty);
+            }
+        }
+        if let Some(record) = game.last_move() {
+            match record.mv {
+                Move::Place(idx) if record.captured.len() == 1 => {
+                    playout.ko = playout.ko_point(idx, record.captured[0]);
+                }
+                Move::Place(_) => {}
+                Move::Pass => playout.passes = 1,
+            }
+        }
+        playout
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.stones.len()
+    }
+
+    pub fn to_move(&self) -> Turn {
+        self.to_move
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.passes >= 2
+    }
+
+    fn remove_empty(&mut self, idx: i32) {
+        let pos = self.empty_pos[idx as usize];
+        let last = self.empty.pop().unwrap();
+        if last != idx {
+            self.empty[pos] = last;
+            self.empty_pos[last as usize] = pos;
+        }
+    }
+
+    fn add_empty(&mut self, idx: i32) {
+        self.empty_pos[idx as usize] = self.empty.len();
+        self.empty.push(idx);
+    }
+
+    fn place_stone(&mut self, idx: i32, ty: StoneType) {
+        self.stones[idx as usize] = ty;
+        self.remove_empty(idx);
+        let mut pseudo_liberties = 0;
+        for k in self.graph.range(idx) {
+            let n = self.graph.neighbors[k];
+            match self.chain[n as usize] {
+                -1 => pseudo_liberties += 1,
+                head => self.pseudo_liberties[head as usize] -= 1,
+            }
+        }
+        self.chain[idx as usize] = idx;
+        self.next_stone[idx as usize] = idx;
+        self.chain_size[idx as usize] = 1;
+        self.pseudo_liberties[idx as usize] = pseudo_liberties;
+        for k in self.graph.range(idx) {
+            let n = self.graph.neighbors[k];
+            if self.stones[n as usize] == ty {
+                self.merge_chains(idx, n);
+            }
+        }
+    }
+
+    fn merge_chains(&mut self, a: i32, b: i32) {
+        let (mut head_a, mut head_b) = (self.chain[a as usize], self.chain[b as usize]);
+        if head_a == head_b {
+            return;
+        }
+        if self.chain_size[head_a as usize] < self.chain_size[head_b as usize] {
+            std::mem::swap(&mut head_a, &mut head_b);
+        }
+        let mut i = head_b;
+        loop {
+            self.chain[i as usize] = head_a;
+            i = self.next_stone[i as usize];
+            if i == head_b {
+                break;
+            }
+        }
+        self.next_stone.swap(head_a as usize, head_b as usize);
+        self.chain_size[head_a as usize] += self.chain_size[head_b as usize];
+        self.pseudo_liberties[head_a as usize] += self.pseudo_liberties[head_b as usize];
+    }
+
+    // returns the number of stones taken off
+    fn remove_chain(&mut self, head: i32) -> u32 {
+        let mut i = head;
+        loop {
+            self.stones[i as usize] = StoneType::Empty;
+            self.chain[i as usize] = -1;
+            self.add_empty(i);
+            i = self.next_stone[i as usize];
+            if i == head {
+                break;
+            }
+        }
+        // next_stone still links the removed stones
+        let mut count = 0;
+        loop {
+            count += 1;
+            for k in self.graph.range(i) {
+                let n = self.graph.neighbors[k];
+                let neighbor_head = self.chain[n as usize];
+                if neighbor_head >= 0 {
+                    self.pseudo_liberties[neighbor_head as usize] += 1;
+                }
+            }
+            i = self.next_stone[i as usize];
+            if i == head {
+                return count;
+            }
+        }
+    }
+
+    fn is_last_liberty(&self, idx: i32, point: i32) -> bool {
+        let mut i = idx;
+        loop {
+            for &n in self.graph.neighbors(i) {
+                if n != point && self.stones[n as usize] == StoneType::Empty {
+                    return false;
+                }
+            }
+            i = self.next_stone[i as usize];
+            if i == idx {
+                return true;
+            }
+        }
+    }
+
+    // a single stone capture, where the capturing stone is left alone in atari
+    fn ko_point(&self, idx: i32, captured: i32) -> i32 {
+        let head = self.chain[idx as usize];
+        if head >= 0
+            && self.chain_size[head as usize] == 1
+            && self.pseudo_liberties[head as usize] == 1
+        {
+            captured
+        } else {
+            -1
+        }
+    }
+
+    pub fn is_legal(&self, idx: i32) -> bool {
+        if self.stones[idx as usize] != StoneType::Empty || idx == self.ko {
+            return false;
+        }
+        let player = self.to_move.stone();
+        self.graph.neighbors(idx).iter().any(|&n| {
+            let ty = self.stones[n as usize];
+            ty == StoneType::Empty || (ty == player) != self.is_last_liberty(n, idx)
+        })
+    }
+
+    pub fn is_own_eye(&self, idx: i32) -> bool {
+        let player = self.to_move.stone();
+        self.graph
+            .neighbors(idx)
+            .iter()
+            .all(|&n| self.stones[n as usize] == player)
+    }
+
+    // the move has to be legal
+    pub fn play(&mut self, idx: i32) {
+        let player = self.to_move.stone();
+        let opponent = self.to_move.other().stone();
+        self.place_stone(idx, player);
+        let (mut captured, mut captured_point) = (0, -1);
+        for k in self.graph.range(idx) {
+            let n = self.graph.neighbors[k];
+            let head = self.chain[n as usize];
+            if self.stones[n as usize] == opponent && self.pseudo_liberties[head as usize] == 0 {
+                captured += self.remove_chain(head);
+                captured_point = n;
+            }
+        }
+        self.ko = if captured == 1 {
+            self.ko_point(idx, captured_point)
+        } else {
+            -1
+        };
+        self.passes = 0;
+        self.to_move = self.to_move.other();
+    }
+
+    pub fn pass(&mut self) {
+        self.ko = -1;
+        self.passes += 1;
+        self.to_move = self.to_move.other();
+    }
+
+    // random legal move outside the player's own eyes, -1 to pass
+    pub fn random_move(&self, rng_state: &mut u64) -> i32 {
+        let count = self.empty.len();
+        if count == 0 {
+            return -1;
+        }
+        let start = (next_key(rng_state) % count as u64) as usize;
+        for k in 0..count {
+            let idx = self.empty[(start + k) % count];
+            if !self.is_own_eye(idx) && self.is_legal(idx) {
+                return idx;
+            }
+        }
+        -1
+    }
+
+    pub fn playout(&mut self, rng_state: &mut u64) {
+        let max_moves = PLAYOUT_MOVES_PER_POINT * self.point_count();
+        for _ in 0..max_moves {
+            if self.is_over() {
+                return;
+            }
+            match self.random_move(rng_state) {
+                -1 => self.pass(),
+                idx => self.play(idx),
+            }
+        }
+    }
+
+    // 1 for black, -1 for white, for stones and the empty points they surround
+    pub fn owner(&self, idx: i32) -> i32 {
+        let owner_of = |ty| match ty {
+            StoneType::Black => 1,
+            StoneType::White => -1,
+            StoneType::Empty => 0,
+        };
+        match self.stones[idx as usize] {
+            StoneType::Empty => {
+                let mut neighbors = self.graph.neighbors(idx).iter();
+                let first = neighbors
+                    .next()
+                    .map_or(0, |&n| owner_of(self.stones[n as usize]));
+                if neighbors.all(|&n| owner_of(self.stones[n as usize]) == first) {
+                    first
+                } else {
+                    0
+                }
+            }
+            ty => owner_of(ty),
+        }
+    }
+
+    // area score, positive when black is ahead
+    pub fn score(&self) -> f64 {
+        let area: i32 = (0..self.point_count() as i32).map(|i| self.owner(i)).sum();
+        area as f64 - self.komi
+    }
+}
+
+struct Node {
+    // -1 for a pass, ignored on the root
+    mv: i32,
+    // who played mv
+    player: Turn,
+    first_child: usize,
+    child_count: usize,
+    visits: u32,
+    wins: f64,
+}
+
+impl Node {
+    fn new(mv: i32, player: Turn) -> Self {
+        Self {
+            mv,
+            player,
+            first_child: 0,
+            child_count: 0,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+// uct over a tree in one arena, returns (move, visits, wins) for the root moves
+fn run_tree(
+    root: &PlayoutBoard,
+    root_moves: &[i32],
+    params: &SearchParams,
+    playouts: u32,
+    seed: u64,
+) -> Vec<(i32, u32, f64)> {
+    let mut rng_state = seed;
+    let mut nodes = vec![Node::new(-1, root.to_move.other())];
+    nodes[0].first_child = 1;
+    nodes[0].child_count = root_moves.len() + 1;
+    for &mv in root_moves.iter().chain([-1].iter()) {
+        nodes.push(Node::new(mv, root.to_move));
+    }
+
+    let mut scratch = root.clone();
+    let mut path = vec![];
+    for _ in 0..playouts {
+        scratch.clone_from(root);
+        path.clear();
+        path.push(0);
+        let mut node = 0;
+        while !scratch.is_over() {
+            if nodes[node].child_count == 0 {
+                if nodes[node].visits == 0 {
+                    break;
+                }
+                let first_child = nodes.len();
+                for k in 0..scratch.empty.len() {
+                    let idx = scratch.empty[k];
+                    if !scratch.is_own_eye(idx) && scratch.is_legal(idx) {
+                        nodes.push(Node::new(idx, scratch.to_move));
+                    }
+                }
+                nodes.push(Node::new(-1, scratch.to_move));
+                nodes[node].first_child = first_child;
+                nodes[node].child_count = nodes.len() - first_child;
+            }
+
+            let parent = &nodes[node];
+            let log_visits = (parent.visits.max(1) as f64).ln();
+            let mut best = parent.first_child;
+            let mut best_value = f64::NEG_INFINITY;
+            let children = parent.first_child..parent.first_child + parent.child_count;
+            for (child, c) in children.clone().zip(&nodes[children]) {
+                if c.visits == 0 {
+                    best = child;
+                    break;
+                }
+                let value = c.wins / c.visits as f64
+                    + params.exploration * (log_visits / c.visits as f64).sqrt();
+                if value > best_value {
+                    best = child;
+                    best_value = value;
+                }
+            }
+            node = best;
+            path.push(node);
+            match nodes[node].mv {
+                -1 => scratch.pass(),
+                idx => scratch.play(idx),
+            }
+            if nodes[node].visits == 0 {
+                break;
+            }
+        }
+
+        scratch.playout(&mut rng_state);
+        let winner = if scratch.score() > 0.0 {
+            Turn::Black
+        } else {
+            Turn::White
+        };
+        for &n in &path {
+            nodes[n].visits += 1;
+            if nodes[n].player == winner {
+                nodes[n].wins += 1.0;
+            }
+        }
+    }
+
+    let root = &nodes[0];
+    nodes[root.first_child..root.first_child + root.child_count]
+        .iter()
+        .map(|c| (c.mv, c.visits, c.wins))
+        .collect()
+}
+
+// picks a move for the player to move, the same seed gives the same result
+pub fn search<SpinorT: Spinor>(
+    game: &GameState<SpinorT>,
+    params: &SearchParams,
+    seed: u64,
+) -> SearchResult {
+    SearchRoot::new(game).search(params, seed)
+}
+
+// a position detached from its game, so the search can run on another thread
+pub struct SearchRoot {
+    board: PlayoutBoard,
+    moves: Vec<i32>,
+    finished: bool,
+}
+
+impl SearchRoot {
+    pub fn new<SpinorT: Spinor>(game: &GameState<SpinorT>) -> Self {
+        let board = PlayoutBoard::from_game(game);
+        let moves = game
+            .legal_moves()
+            .filter(|&i| !board.is_own_eye(i))
+            .collect();
+        Self {
+            board,
+            moves,
+            finished: game.phase() == GamePhase::Finished,
+        }
+    }
+
+    pub fn search(&self, params: &SearchParams, seed: u64) -> SearchResult {
+        let (root, root_moves) = (&self.board, &self.moves[..]);
+        if self.finished {
+            return SearchResult {
+                best: Move::Pass,
+                moves: vec![],
+            };
+        }
+
+        // no threads on the web
+        let threads = if cfg!(target_arch = "wasm32") {
+            1
+        } else {
+            params.threads.clamp(1, params.playouts.max(1))
+        };
+        let mut seed_state = seed;
+        let seeds: Vec<u64> = (0..threads).map(|_| next_key(&mut seed_state)).collect();
+        let share = |t: u32| params.playouts / threads + (t < params.playouts % threads) as u32;
+        let totals = if threads == 1 {
+            run_tree(root, root_moves, params, params.playouts, seeds[0])
+        } else {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..threads)
+                    .map(|t| {
+                        let seed = seeds[t as usize];
+                        scope.spawn(move || run_tree(root, root_moves, params, share(t), seed))
+                    })
+                    .collect();
+                let mut totals: Vec<(i32, u32, f64)> = vec![];
+                for handle in handles {
+                    let tree = handle.join().unwrap();
+                    if totals.is_empty() {
+                        totals = tree;
+                    } else {
+                        for (total, (_, visits, wins)) in totals.iter_mut().zip(tree) {
+                            total.1 += visits;
+                            total.2 += wins;
+                        }
+                    }
+                }
+                totals
+            })
+        };
+
+        let mut moves: Vec<(Move, u32, f64)> = totals
+            .into_iter()
+            .map(|(mv, visits, wins)| {
+                let mv = if mv < 0 { Move::Pass } else { Move::Place(mv) };
+                let rate = if visits > 0 {
+                    wins / visits as f64
+                } else {
+                    0.5
+                };
+                (mv, visits, rate)
+            })
+            .collect();
+        moves.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+        SearchResult {
+            best: moves.first().map_or(Move::Pass, |m| m.0),
+            moves,
+        }
+    }
+}
+
+// averaged over random playouts from a position
+#[derive(Clone, Debug)]
+pub struct Ownership {
+    // from -1 for white to 1 for black, for each point
+    pub points: Vec<f64>,
+    // expected area margin after komi, positive when black is ahead
+    pub score: f64,
+    // the position the estimate is for
+    hash: u64,
+    turn: Turn,
+}
+
+pub fn estimate_ownership<SpinorT: Spinor>(
+    game: &GameState<SpinorT>,
+    playouts: u32,
+    seed: u64,
+) -> Ownership {
+    let root = PlayoutBoard::from_game(game);
+    let mut scratch = root.clone();
+    let mut rng_state = seed;
+    let playouts = playouts.max(1);
+    let mut points = vec![0.0; root.point_count()];
+    let mut score = 0.0;
+    for _ in 0..playouts {
+        scratch.clone_from(&root);
+        scratch.playout(&mut rng_state);
+        for (i, owner) in points.iter_mut().enumerate() {
+            *owner += scratch.owner(i as i32) as f64;
+        }
+        score += scratch.score();
+    }
+    for owner in &mut points {
+        *owner /= playouts as f64;
+    }
+    Ownership {
+        points,
+        score: score / playouts as f64,
+        hash: game.board.hash,
+        turn: game.turn,
+    }
+}
+
+impl<SpinorT: Spinor> GameState<SpinorT> {
+    // the estimate is kept until the position changes
+    pub fn update_ownership(&mut self, playouts: u32, seed: u64) {
+        let current = self
+            .ownership
+            .as_ref()
+            .is_some_and(|o| o.hash == self.board.hash && o.turn == self.turn);
+        if !current {
+            self.ownership = Some(estimate_ownership(self, playouts, seed));
+            self.needs_render = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    #[test]
+    fn test_playout_board_matches_game() {
+        let params = TilingParameters::new::<SpinorHyperbolic>(5, 5, 4);
+        let mut game = GameState::<SpinorHyperbolic>::new(params, 0);
+        game.rules.ko_rule = KoRule::Simple;
+        let mut playout = PlayoutBoard::from_game(&game);
+        let mut state = 3;
+        for _ in 0..500 {
+            let idx = (next_key(&mut state) % game.point_count() as u64) as i32;
+            assert_eq!(playout.is_legal(idx), game.is_legal(idx).is_ok());
+            if game.play_point(idx) {
+                playout.play(idx);
+            }
+            for i in 0..game.point_count() as i32 {
+                assert_eq!(playout.stones[i as usize], game.board().stone(i));
+            }
+        }
+        let copy = PlayoutBoard::from_game(&game);
+        assert_eq!(copy.ko, playout.ko);
+        assert_eq!(copy.empty.len(), playout.empty.len());
+    }
+
+    #[test]
+    fn test_search() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        let mut state = 11;
+        for _ in 0..10 {
+            let mv = Player::Random.choose_move(&game, &SearchParams::default(), &mut state);
+            game.play_move(mv.unwrap());
+        }
+
+        let search_params = SearchParams {
+            playouts: 500,
+            threads: 1,
+            ..Default::default()
+        };
+        let result = search(&game, &search_params, 1);
+        let visits: u32 = result.moves.iter().map(|m| m.1).sum();
+        assert_eq!(visits, 500);
+        assert_eq!(search(&game, &search_params, 1).moves, result.moves);
+        match result.best {
+            Move::Place(idx) => assert!(game.is_legal(idx).is_ok()),
+            Move::Pass => panic!("passed on an open board"),
+        }
+
+        let search_params = SearchParams {
+            threads: 3,
+            ..search_params
+        };
+        let result = search(&game, &search_params, 2);
+        let visits: u32 = result.moves.iter().map(|m| m.1).sum();
+        assert_eq!(visits, 500);
+
+        // nothing but its own eyes left, black passes
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        let board = game.board();
+        let eyes: Vec<i32> = (0..25).filter(|&i| board.neighbors(i).len() == 2).collect();
+        for i in 0..25 {
+            if !eyes.contains(&i) {
+                assert!(game.play_point(i));
+                game.set_turn(Turn::Black);
+            }
+        }
+        let result = search(&game, &search_params, 3);
+        assert_eq!(result.best, Move::Pass);
+
+        let ownership = estimate_ownership(&game, 100, 4);
+        assert!(ownership.points.iter().all(|&owner| owner == 1.0));
+        assert_eq!(ownership.score, 25.0 - game.komi);
+    }
+
+    #[test]
+    fn test_ownership() {
+        let params = TilingParameters::new::<SpinorEuclidian>(5, 4, 4);
+        let mut game = GameState::<SpinorEuclidian>::new(params, 0);
+        // a black wall on the second column, a white one on the fourth
+        for row in 0..5 {
+            assert!(game.play_point(game.point_at_grid(1, row).unwrap()));
+            assert!(game.play_point(game.point_at_grid(3, row).unwrap()));
+        }
+        game.update_ownership(500, 1);
+        let ownership = game.ownership.as_ref().unwrap();
+        for row in 0..5 {
+            assert!(ownership.points[game.point_at_grid(0, row).unwrap() as usize] > 0.5);
+            assert!(ownership.points[game.point_at_grid(4, row).unwrap() as usize] < -0.5);
+        }
+        // even apart from komi
+        assert!((ownership.score + game.komi).abs() < 2.0);
+
+        // only redone for a new position
+        let score = ownership.score;
+        game.update_ownership(10, 2);
+        assert_eq!(game.ownership.as_ref().unwrap().score, score);
+        game.pass_move();
+        game.update_ownership(10, 2);
+        assert_ne!(game.ownership.as_ref().unwrap().score, score);
+    }
+}
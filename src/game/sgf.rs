@@ -0,0 +1,592 @@
+use std::fmt::{self, Write};
+
+use super::*;
+
+// custom root properties, see to_sgf
+const GEOMETRY_PROP: &str = "HG";
+const TILING_PROP: &str = "HT";
+const BOUNDARY_PROP: &str = "HB";
+const CENTER_PROP: &str = "HC";
+
+#[derive(Debug, PartialEq)]
+pub enum SgfError {
+    Syntax(usize),
+    MissingProperty(&'static str),
+    BadValue(String),
+    WrongGeometry(String),
+    IllegalMove(String),
+    // setup we can't represent, loading without it would give the wrong position
+    Unsupported(String),
+}
+
+impl fmt::Display for SgfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SgfError::Syntax(pos) => write!(f, "syntax error at byte {}", pos),
+            SgfError::MissingProperty(prop) => write!(f, "missing property {}", prop),
+            SgfError::BadValue(v) => write!(f, "bad property value {:?}", v),
+            SgfError::WrongGeometry(g) => write!(f, "game uses {} geometry", g),
+            SgfError::IllegalMove(v) => write!(f, "illegal move {:?}", v),
+            SgfError::Unsupported(prop) => write!(f, "unsupported property {}", prop),
+        }
+    }
+}
+
+struct SgfNode {
+    props: Vec<(String, Vec<String>)>,
+    children: Vec<SgfNode>,
+}
+
+impl SgfNode {
+    fn get(&self, ident: &str) -> Option<&str> {
+        self.get_all(ident).first().map(|v| v.as_str())
+    }
+
+    fn get_all(&self, ident: &str) -> &[String] {
+        self.props
+            .iter()
+            .find(|(i, _)| i == ident)
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.src.len() && self.src[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.src.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), SgfError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(SgfError::Syntax(self.pos))
+        }
+    }
+
+    // "(" node+ game_tree* ")", sequences become chains of single children
+    fn game_tree(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(b'(')?;
+        let mut sequence = vec![self.node()?];
+        while self.peek() == Some(b';') {
+            sequence.push(self.node()?);
+        }
+        let mut last = sequence.pop().unwrap();
+        while self.peek() == Some(b'(') {
+            last.children.push(self.game_tree()?);
+        }
+        self.expect(b')')?;
+        while let Some(mut node) = sequence.pop() {
+            node.children.push(last);
+            last = node;
+        }
+        Ok(last)
+    }
+
+    fn node(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(b';')?;
+        let mut props = vec![];
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_uppercase() {
+                break;
+            }
+            let start = self.pos;
+            while self.pos < self.src.len() && self.src[self.pos].is_ascii_uppercase() {
+                self.pos += 1;
+            }
+            let ident = String::from_utf8_lossy(&self.src[start..self.pos]).into_owned();
+            let mut values = vec![];
+            while self.peek() == Some(b'[') {
+                values.push(self.value()?);
+            }
+            if values.is_empty() {
+                return Err(SgfError::Syntax(self.pos));
+            }
+            props.push((ident, values));
+        }
+        Ok(SgfNode {
+            props,
+            children: vec![],
+        })
+    }
+
+    fn value(&mut self) -> Result<String, SgfError> {
+        self.expect(b'[')?;
+        let mut bytes = vec![];
+        loop {
+            match self.src.get(self.pos) {
+                None => return Err(SgfError::Syntax(self.pos)),
+                Some(b']') => break,
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.src.get(self.pos) {
+                        None => return Err(SgfError::Syntax(self.pos)),
+                        // soft line break
+                        Some(b'\n') => {}
+                        Some(&c) => bytes.push(c),
+                    }
+                }
+                Some(&c) => bytes.push(c),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn geometry_name(geometry: Geometry) -> &'static str {
+    match geometry {
+        Geometry::Euclidian => "euclidian",
+        Geometry::Hyperbolic => "hyperbolic",
+        Geometry::Spherical => "spherical",
+    }
+}
+
+fn geometry_from_name(name: &str) -> Option<Geometry> {
+    [
+        Geometry::Euclidian,
+        Geometry::Hyperbolic,
+        Geometry::Spherical,
+    ]
+    .into_iter()
+    .find(|&g| geometry_name(g) == name)
+}
+
+fn parse_root(sgf: &str) -> Result<SgfNode, SgfError> {
+    let mut parser = Parser {
+        src: sgf.as_bytes(),
+        pos: 0,
+    };
+    parser.game_tree()
+}
+
+// geometry a record was played in, to pick the GameState type to load it into
+pub fn sgf_geometry(sgf: &str) -> Result<Geometry, SgfError> {
+    match parse_root(sgf)?.get(GEOMETRY_PROP) {
+        Some(name) => geometry_from_name(name).ok_or_else(|| SgfError::BadValue(name.into())),
+        // plain sgf from other software
+        None => Ok(Geometry::Euclidian),
+    }
+}
+
+impl<SpinorT: Spinor> Board<SpinorT> {
+    fn point_to_sgf(&self, idx: i32) -> String {
+        match self.grid_coords(idx) {
+            Some((col, row)) => format!(
+                "{}{}",
+                (b'a' + col as u8) as char,
+                (b'a' + row as u8) as char
+            ),
+            None => self.point_name(idx).to_string(),
+        }
+    }
+
+    // older records pass with tt on boards up to 19x19
+    fn is_sgf_pass(&self, value: &str) -> bool {
+        value.is_empty()
+            || (value == "tt" && self.is_square_grid() && self.tiling_parameters.edge_count <= 19)
+    }
+
+    fn point_from_sgf(&self, value: &str) -> Option<i32> {
+        if self.is_square_grid() {
+            let bytes = value.as_bytes();
+            if bytes.len() != 2 || !bytes.iter().all(|c| c.is_ascii_lowercase()) {
+                return None;
+            }
+            self.point_at_grid((bytes[0] - b'a') as u32, (bytes[1] - b'a') as u32)
+        } else if let Ok(name) = value.parse() {
+            self.point_by_name(name)
+        } else {
+            // older records used the point index
+            let idx = value.parse::<i32>().ok()?;
+            (idx >= 0 && idx < self.points.len() as i32).then_some(idx)
+        }
+    }
+
+    fn move_to_sgf(&self, record: &MoveRecord) -> String {
+        let color = match record.player {
+            Turn::Black => "B",
+            Turn::White => "W",
+        };
+        match record.mv {
+            Move::Place(idx) => format!(";{}[{}]", color, self.point_to_sgf(idx)),
+            Move::Pass => format!(";{}[]", color),
+        }
+    }
+}
+
+impl<SpinorT: Spinor> GameState<SpinorT> {
+    // RU[rules] and KM[komi] come first, the tiling goes in HG[geometry] and
+    // HT[sides:around_vertex:edge_count], followed by HB[boundary] and HC[center]
+    // when they aren't the defaults for edge_count, moves
+    // are point names like 3-17 except on {4,4} euclidian boards, which get plain sgf
+    // coords
+    pub fn to_sgf(&self) -> String {
+        let board = &self.board;
+        let params = &board.tiling_parameters;
+        let mut out = format!(
+            "(;FF[4]GM[1]CA[UTF-8]AP[hypergo:{}]RU[{}]KM[{}]{}[{}]{}[{}:{}:{}]",
+            env!("CARGO_PKG_VERSION"),
+            self.rules,
+            self.komi,
+            GEOMETRY_PROP,
+            geometry_name(SpinorT::geometry()),
+            TILING_PROP,
+            params.sides,
+            params.around_vertex,
+            params.edge_count,
+        );
+        if params.boundary != Boundary::Rings {
+            write!(out, "{}[{}]", BOUNDARY_PROP, params.boundary).unwrap();
+        }
+        if params.center != BoardCenter::for_edge_count(params.edge_count) {
+            write!(out, "{}[{}]", CENTER_PROP, params.center).unwrap();
+        }
+        if board.is_square_grid() {
+            write!(out, "SZ[{}]", params.edge_count).unwrap();
+        }
+        if !board.setup_stones.is_empty() {
+            write!(out, "HA[{}]AB", self.handicap).unwrap();
+            for &idx in &board.setup_stones {
+                write!(out, "[{}]", board.point_to_sgf(idx)).unwrap();
+            }
+        }
+        if let Some(result) = &self.result {
+            write!(out, "RE[{}]", result).unwrap();
+        }
+
+        // (node, whether it needs its own variation parens), or None to close one
+        let mut stack = vec![];
+        let push_children = |stack: &mut Vec<Option<(usize, bool)>>, node: usize| {
+            let children = board.tree.children(node);
+            let branching = children.len() > 1;
+            for &child in children.iter().rev() {
+                stack.push(Some((child, branching)));
+            }
+        };
+        push_children(&mut stack, ROOT_NODE);
+        while let Some(entry) = stack.pop() {
+            match entry {
+                Some((node, branching)) => {
+                    if branching {
+                        out.push('(');
+                        stack.push(None);
+                    }
+                    out.push_str(&board.move_to_sgf(board.tree.record(node).unwrap()));
+                    push_children(&mut stack, node);
+                }
+                None => out.push(')'),
+            }
+        }
+        out.push_str(")\n");
+        out
+    }
+
+    pub fn from_sgf(sgf: &str) -> Result<Self, SgfError> {
+        let root = parse_root(sgf)?;
+
+        let tiling_parameters = match root.get(TILING_PROP) {
+            Some(value) => {
+                let geometry = root
+                    .get(GEOMETRY_PROP)
+                    .ok_or(SgfError::MissingProperty(GEOMETRY_PROP))?;
+                if geometry != geometry_name(SpinorT::geometry()) {
+                    return Err(SgfError::WrongGeometry(geometry.into()));
+                }
+                let parts = value
+                    .split(':')
+                    .map(|v| v.trim().parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| SgfError::BadValue(value.into()))?;
+                if parts.len() != 3 {
+                    return Err(SgfError::BadValue(value.into()));
+                }
+                let mut params = TilingParameters::new::<SpinorT>(parts[2], parts[0], parts[1]);
+                if let Some(value) = root.get(BOUNDARY_PROP) {
+                    params.boundary = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| SgfError::BadValue(value.into()))?;
+                }
+                if let Some(value) = root.get(CENTER_PROP) {
+                    params.center = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| SgfError::BadValue(value.into()))?;
+                }
+                params
+            }
+            // plain sgf from other software
+            None => {
+                if SpinorT::geometry() != Geometry::Euclidian {
+                    return Err(SgfError::WrongGeometry(
+                        geometry_name(Geometry::Euclidian).into(),
+                    ));
+                }
+                let size = root.get("SZ").unwrap_or("19");
+                let edge_count = size
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| SgfError::BadValue(size.into()))?;
+                TilingParameters::new::<SpinorT>(edge_count, 4, 4)
+            }
+        };
+        let mut game = GameState::new(tiling_parameters, 0);
+        // rule sets we don't know keep the defaults
+        if let Some(rules) = root.get("RU").and_then(|value| value.parse::<Rules>().ok()) {
+            game.rules = rules;
+            game.komi = rules.scoring_rule.default_komi();
+        }
+        for value in root.get_all("AB") {
+            let idx = game
+                .board
+                .point_from_sgf(value.trim())
+                .filter(|&idx| game.board.stone(idx) == StoneType::Empty)
+                .ok_or_else(|| SgfError::BadValue(value.clone()))?;
+            game.board.add_setup_stone(idx, StoneType::Black);
+        }
+        if let Some(value) = root.get("HA") {
+            game.handicap = value
+                .trim()
+                .parse()
+                .map_err(|_| SgfError::BadValue(value.into()))?;
+            if game.handicap > 0 {
+                game.komi = HANDICAP_KOMI;
+                game.start_after_handicap();
+            }
+        }
+        if let Some(value) = root.get("KM") {
+            game.komi = value
+                .trim()
+                .parse()
+                .map_err(|_| SgfError::BadValue(value.into()))?;
+        }
+        game.add_sgf_moves(&root, true)?;
+
+        let mut end = ROOT_NODE;
+        while let Some(&child) = game.board.tree.children(end).first() {
+            end = child;
+        }
+        game.board.go_to_node(end);
+        game.turn = game.board.next_turn();
+        game.score = None;
+        // results we don't understand, like forfeits, are dropped
+        game.result = root.get("RE").and_then(|value| value.trim().parse().ok());
+        Ok(game)
+    }
+
+    // only black setup stones in the root are supported, as handicap stones
+    fn add_sgf_moves(&mut self, node: &SgfNode, at_root: bool) -> Result<(), SgfError> {
+        let mut node = node;
+        let mut at_root = at_root;
+        loop {
+            for (ident, values) in &node.props {
+                let player = match ident.as_str() {
+                    "B" => Turn::Black,
+                    "W" => Turn::White,
+                    "AB" if at_root => continue,
+                    "AB" | "AW" | "AE" => return Err(SgfError::Unsupported(ident.clone())),
+                    _ => continue,
+                };
+                let value = values[0].trim();
+                self.turn = player;
+                if self.board.is_sgf_pass(value) {
+                    self.pass_move();
+                } else {
+                    let idx = self
+                        .board
+                        .point_from_sgf(value)
+                        .ok_or_else(|| SgfError::BadValue(value.into()))?;
+                    if !self.play_point(idx) {
+                        return Err(SgfError::IllegalMove(value.into()));
+                    }
+                }
+            }
+            at_root = false;
+            match node.children.len() {
+                0 => return Ok(()),
+                1 => node = &node.children[0],
+                _ => break,
+            }
+        }
+
+        let branch_point = self.board.tree.current();
+        for child in &node.children {
+            self.add_sgf_moves(child, false)?;
+            self.board.go_to_node(branch_point);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    #[test]
+    fn test_sgf_coords_round_trip() {
+        let sgf = "(;FF[4]GM[1]SZ[5];B[cc];W[dc](;B[cd];W[])(;B[bb]))";
+        let game = GameState::<SpinorEuclidian>::from_sgf(sgf).unwrap();
+        assert_eq!(game.moves().len(), 4);
+        assert_eq!(game.board.points[0].ty, StoneType::Black);
+
+        let out = game.to_sgf();
+        assert!(out.contains("SZ[5]"));
+        assert!(out.contains(";B[cc];W[dc](;B[cd];W[])(;B[bb]))"));
+        let reloaded = GameState::<SpinorEuclidian>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.to_sgf(), out);
+    }
+
+    #[test]
+    fn test_sgf_hyperbolic() {
+        let mut game = GameState::<SpinorHyperbolic>::new(
+            TilingParameters::new::<SpinorHyperbolic>(5, 5, 4),
+            0,
+        );
+        assert!(game.play_point(7));
+        assert!(game.play_point(0));
+        game.pass_move();
+
+        let out = game.to_sgf();
+        let first = game.point_name(7).to_string();
+        assert!(out.contains("HG[hyperbolic]HT[5:4:5]"));
+        assert!(out.contains(&format!(";B[{}];W[1-1];B[])", first)));
+        let reloaded = GameState::<SpinorHyperbolic>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.to_sgf(), out);
+        // older records have point indices
+        let old = out.replace(&first, "7").replace("1-1", "0");
+        let reloaded = GameState::<SpinorHyperbolic>::from_sgf(&old).unwrap();
+        assert_eq!(reloaded.to_sgf(), out);
+        assert_eq!(
+            GameState::<SpinorEuclidian>::from_sgf(&out).err(),
+            Some(SgfError::WrongGeometry("hyperbolic".into()))
+        );
+        assert_eq!(sgf_geometry(&out), Ok(Geometry::Hyperbolic));
+        assert_eq!(sgf_geometry("(;SZ[9])"), Ok(Geometry::Euclidian));
+    }
+
+    #[test]
+    fn test_sgf_boundary() {
+        let even = GameState::<SpinorEuclidian>::from_sgf("(;SZ[4];B[dd])").unwrap();
+        assert_eq!(even.point_count(), 16);
+
+        let params = TilingParameters::new::<SpinorHyperbolic>(1, 5, 4)
+            .with_center(BoardCenter::Face)
+            .with_boundary(Boundary::PointCount(81));
+        let mut game = GameState::<SpinorHyperbolic>::new(params, 0);
+        assert!(game.play_point(80));
+        let out = game.to_sgf();
+        let name = game.point_name(80);
+        assert!(out.contains(&format!("HT[5:4:1]HB[points:81]HC[face];B[{}]", name)));
+        let reloaded = GameState::<SpinorHyperbolic>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.point_count(), 81);
+        assert_eq!(reloaded.to_sgf(), out);
+        assert_eq!(
+            GameState::<SpinorHyperbolic>::from_sgf("(;HG[hyperbolic]HT[5:4:1]HB[points:0])").err(),
+            Some(SgfError::BadValue("points:0".into()))
+        );
+    }
+
+    #[test]
+    fn test_sgf_result() {
+        let mut game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[5];B[cc])").unwrap();
+        game.resign(Turn::White);
+        let out = game.to_sgf();
+        assert!(out.contains("SZ[5]RE[B+R];B[cc]"));
+        let reloaded = GameState::<SpinorEuclidian>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.phase(), GamePhase::Finished);
+        assert_eq!(reloaded.to_sgf(), out);
+
+        let game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[5]RE[W+F])").unwrap();
+        assert_eq!(game.result, None);
+    }
+
+    #[test]
+    fn test_sgf_setup() {
+        let game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[19];B[dd];W[tt];B[])").unwrap();
+        assert_eq!(game.moves()[1].mv, Move::Pass);
+        assert_eq!(game.moves()[2].mv, Move::Pass);
+        // tt is a real point on bigger boards
+        let game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[21];B[tt])").unwrap();
+        assert_eq!(
+            game.moves()[0].mv,
+            Move::Place(game.point_at_grid(19, 19).unwrap())
+        );
+
+        for sgf in [
+            "(;SZ[9]AW[aa])",
+            "(;SZ[9]AB[aa]AE[aa])",
+            "(;SZ[9];B[bb];AB[aa])",
+        ] {
+            let prop = &sgf[sgf.rfind('A').unwrap()..sgf.rfind('[').unwrap()];
+            assert_eq!(
+                GameState::<SpinorEuclidian>::from_sgf(sgf).err(),
+                Some(SgfError::Unsupported(prop.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_sgf_rules() {
+        let mut game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[5])").unwrap();
+        game.rules = Rules::new_zealand();
+        game.komi = 0.5;
+        // black's second stone in the corner takes its last liberty
+        for point in ["aa", "ab", "ee", "bb", "dd", "ca", "ba"] {
+            let idx = game.board.point_from_sgf(point).unwrap();
+            assert!(game.play_point(idx));
+        }
+        assert_eq!(game.moves().last().unwrap().self_captured.len(), 2);
+        let out = game.to_sgf();
+        assert!(out.contains("RU[area:situational-superko:suicide]KM[0.5]"));
+        let reloaded = GameState::<SpinorEuclidian>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.rules, Rules::new_zealand());
+        assert_eq!(reloaded.komi, 0.5);
+        assert_eq!(reloaded.to_sgf(), out);
+
+        let game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[5]RU[Japanese])").unwrap();
+        assert_eq!(game.rules, Rules::japanese());
+        assert_eq!(game.komi, 6.5);
+        let game = GameState::<SpinorEuclidian>::from_sgf("(;SZ[5]RU[Ing]KM[8])").unwrap();
+        assert_eq!((game.rules, game.komi), (Rules::default(), 8.0));
+    }
+
+    #[test]
+    fn test_sgf_handicap() {
+        let params = TilingParameters::new::<SpinorHyperbolic>(5, 5, 4);
+        let mut game = GameState::<SpinorHyperbolic>::new(params, 3);
+        let first_move = (0..game.point_count() as i32)
+            .find(|&i| game.board.stone(i) == StoneType::Empty)
+            .unwrap();
+        assert!(game.play_point(first_move));
+        let out = game.to_sgf();
+        assert!(out.contains("HA[3]AB["));
+        assert!(out.contains(&format!(";W[{}])", game.point_name(first_move))));
+
+        let mut reloaded = GameState::<SpinorHyperbolic>::from_sgf(&out).unwrap();
+        assert_eq!(reloaded.to_sgf(), out);
+        assert_eq!(reloaded.board.setup_stones(), game.board.setup_stones());
+        reloaded.go_to_parent();
+        assert_eq!(reloaded.turn(), Turn::White);
+
+        assert_eq!(
+            GameState::<SpinorEuclidian>::from_sgf("(;SZ[9]HA[2]AB[aa][aa])").err(),
+            Some(SgfError::BadValue("aa".into()))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use super::*;
+
+pub const ROOT_NODE: usize = 0;
+
+struct TreeNode {
+    // None only for the root
+    record: Option<MoveRecord>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // child followed when stepping forward, last visited one by default
+    selected_child: usize,
+    depth: i32,
+}
+
+// all explored lines of play, first child of each node is its main line
+pub struct GameTree {
+    nodes: Vec<TreeNode>,
+    current: usize,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![TreeNode {
+                record: None,
+                parent: None,
+                children: Vec::new(),
+                selected_child: 0,
+                depth: 0,
+            }],
+            current: ROOT_NODE,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn record(&self, node: usize) -> Option<&MoveRecord> {
+        self.nodes[node].record.as_ref()
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes[node].parent
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.nodes[node].children
+    }
+
+    pub fn selected_child(&self, node: usize) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .get(self.nodes[node].selected_child)
+            .copied()
+    }
+
+    pub fn depth(&self, node: usize) -> i32 {
+        self.nodes[node].depth
+    }
+
+    // position of the node among its siblings
+    pub fn sibling_idx(&self, node: usize) -> usize {
+        match self.nodes[node].parent {
+            Some(parent) => self.nodes[parent]
+                .children
+                .iter()
+                .position(|&c| c == node)
+                .unwrap(),
+            None => 0,
+        }
+    }
+
+    pub fn is_mainline(&self, node: usize) -> bool {
+        let mut node = node;
+        while let Some(parent) = self.nodes[node].parent {
+            if self.nodes[parent].children[0] != node {
+                return false;
+            }
+            node = parent;
+        }
+        true
+    }
+
+    // nodes from the root (exclusive) down to the given node (inclusive)
+    pub fn path_to(&self, node: usize) -> Vec<usize> {
+        let mut path = Vec::with_capacity(self.nodes[node].depth as usize);
+        let mut node = node;
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(node);
+            node = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    // reuses an existing child if the same player already explored the same move
+    pub fn add_child(&mut self, parent: usize, record: MoveRecord) -> usize {
+        if let Some(&existing) = self.nodes[parent].children.iter().find(|&&c| {
+            let existing = self.nodes[c].record.as_ref().unwrap();
+            existing.mv == record.mv && existing.player == record.player
+        }) {
+            return existing;
+        }
+        let node = self.nodes.len();
+        self.nodes.push(TreeNode {
+            record: Some(record),
+            parent: Some(parent),
+            children: Vec::new(),
+            selected_child: 0,
+            depth: self.nodes[parent].depth + 1,
+        });
+        self.nodes[parent].children.push(node);
+        node
+    }
+
+    pub fn set_current(&mut self, node: usize) {
+        self.current = node;
+        let mut node = node;
+        while let Some(parent) = self.nodes[node].parent {
+            self.nodes[parent].selected_child = self.sibling_idx(node);
+            node = parent;
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+// fixed seed so hashes are reproducible between runs and platforms
+const ZOBRIST_SEED: u64 = 0x6879_7065_7267_6f00;
+
+// splitmix64, good enough for hash keys and avoids pulling in a rng crate
+pub(super) fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub(super) struct ZobristTable {
+    // black and white keys for each point
+    keys: Vec<[u64; 2]>,
+    white_to_move: u64,
+}
+
+impl ZobristTable {
+    pub(super) fn new(point_count: usize) -> Self {
+        let mut state = ZOBRIST_SEED;
+        let white_to_move = next_key(&mut state);
+        let keys = (0..point_count)
+            .map(|_| [next_key(&mut state), next_key(&mut state)])
+            .collect();
+        Self {
+            keys,
+            white_to_move,
+        }
+    }
+
+    pub(super) fn stone(&self, idx: i32, ty: StoneType) -> u64 {
+        match ty {
+            StoneType::Empty => 0,
+            StoneType::Black => self.keys[idx as usize][0],
+            StoneType::White => self.keys[idx as usize][1],
+        }
+    }
+
+    pub(super) fn situation(&self, hash: u64, to_move: Turn) -> u64 {
+        match to_move {
+            Turn::Black => hash,
+            Turn::White => hash ^ self.white_to_move,
+        }
+    }
+}
@@ -28,6 +28,22 @@ impl Point for PointEuclidian {
         Self { x: x / w, y: y / w }
     }
 
+    fn from_screen(v: Vector2<f64>, projection_factor: f64) -> (Self, bool) {
+        (Self::from_flat_vec((1.0 / projection_factor) * v), false)
+    }
+
+    fn to_screen(&self, projection_factor: f64) -> Option<Vector2<f64>> {
+        Some(projection_factor * vec2(self.x, self.y))
+    }
+
+    fn to_chart(&self) -> Option<Vector2<f64>> {
+        Some(vec2(self.x, self.y))
+    }
+
+    fn chart_ball(&self, radius: f64) -> Option<(Vector2<f64>, f64)> {
+        Some((vec2(self.x, self.y), radius))
+    }
+
     fn angle(&self) -> f64 {
         self.y.atan2(self.x)
     }
@@ -86,6 +102,10 @@ pub struct SpinorEuclidian {
 impl Spinor for SpinorEuclidian {
     type Point = PointEuclidian;
 
+    fn geometry() -> Geometry {
+        Geometry::Euclidian
+    }
+
     fn new(s: f64, xy: f64, yw: f64, wx: f64) -> Self {
         Self { s, xy, yw, wx }
     }
@@ -190,6 +210,22 @@ impl Spinor for SpinorEuclidian {
     fn distance_to_flat(d: f64) -> f64 {
         d
     }
+
+    fn stone_scale(distance: f64) -> f64 {
+        Self::distance_to_flat(distance)
+    }
+
+    fn adjust_projection_factor(projection_factor: f64, amt: f64) -> f64 {
+        projection_factor * (amt + 1.0)
+    }
+
+    fn camera_w_scale(projection_factor: f64) -> f64 {
+        1.0 / projection_factor
+    }
+
+    fn skip_reprojection() -> bool {
+        true
+    }
 }
 
 impl One for SpinorEuclidian {
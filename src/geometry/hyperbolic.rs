@@ -58,6 +58,42 @@ impl Point for PointHyperbolic {
         Self { x, y, w }
     }
 
+    fn from_screen(v: Vector2<f64>, projection_factor: f64) -> (Self, bool) {
+        const LIMIT: f64 = 0.99;
+        let mag2 = v.magnitude2();
+        let clipped = mag2 >= LIMIT;
+        let limited = if clipped {
+            v * (LIMIT / mag2).sqrt()
+        } else {
+            v
+        };
+        let base = (0.5 * (1.0 + mag2.min(LIMIT))) * projection_factor + 1.0 - projection_factor;
+        (Self::from_flat_vec(limited / base), clipped)
+    }
+
+    fn to_screen(&self, projection_factor: f64) -> Option<Vector2<f64>> {
+        Some(flat_to_screen(
+            vec2(self.x, self.y) / self.w,
+            projection_factor,
+        ))
+    }
+
+    fn to_chart(&self) -> Option<Vector2<f64>> {
+        Some(vec2(self.x, self.y) / (1.0 + self.w))
+    }
+
+    // the ball's nearest and farthest points from the origin bound the disk
+    fn chart_ball(&self, radius: f64) -> Option<(Vector2<f64>, f64)> {
+        let r = self.w.max(1.0).acosh();
+        let v = vec2(self.x, self.y);
+        if v.magnitude2() < 1e-24 {
+            return Some((vec2(0.0, 0.0), (0.5 * radius).tanh()));
+        }
+        let r_in = (0.5 * (r - radius)).tanh();
+        let r_out = (0.5 * (r + radius)).tanh();
+        Some((v.normalize() * (0.5 * (r_in + r_out)), 0.5 * (r_out - r_in)))
+    }
+
     fn angle(&self) -> f64 {
         self.y.atan2(self.x)
     }
@@ -121,6 +157,10 @@ pub struct SpinorHyperbolic {
 impl Spinor for SpinorHyperbolic {
     type Point = PointHyperbolic;
 
+    fn geometry() -> Geometry {
+        Geometry::Hyperbolic
+    }
+
     fn new(s: f64, xy: f64, yw: f64, wx: f64) -> Self {
         Self { s, xy, yw, wx }
     }
@@ -290,6 +330,22 @@ impl Spinor for SpinorHyperbolic {
     fn distance_to_flat(d: f64) -> f64 {
         d.sinh() / d.cosh()
     }
+
+    fn stone_scale(distance: f64) -> f64 {
+        1.5 * Self::distance_to_flat(distance)
+    }
+
+    fn adjust_projection_factor(projection_factor: f64, amt: f64) -> f64 {
+        (projection_factor + amt).clamp(0.0, 1.0)
+    }
+
+    fn camera_w_scale(_projection_factor: f64) -> f64 {
+        1.0
+    }
+
+    fn skip_reprojection() -> bool {
+        false
+    }
 }
 
 impl One for SpinorHyperbolic {
@@ -368,4 +424,15 @@ mod tests {
         let b = PointHyperbolic::from_flat(0.0, 0.7861513777574233);
         assert_lt!(a.distance(b), 1.0);
     }
+
+    #[test]
+    fn test_to_screen() {
+        for projection_factor in [0.0, 0.5, 1.0] {
+            let v = vec2(0.3, -0.5);
+            let (p, clipped) = PointHyperbolic::from_screen(v, projection_factor);
+            assert!(!clipped);
+            let back = p.to_screen(projection_factor).unwrap();
+            assert_abs_diff_eq!(back, v, epsilon = 1e-9);
+        }
+    }
 }
@@ -1,15 +1,64 @@
 use std::f64::consts::PI;
-use std::fmt::{Debug, Display};
+use std::fmt::{self, Debug, Display};
 use std::ops;
+use std::str::FromStr;
 
-use cgmath::{num_traits::AsPrimitive, vec2, AbsDiffEq, BaseFloat, Matrix4, One, Vector2};
-use cgmath::{InnerSpace, Vector3, Zero};
+use cgmath::{
+    num_traits::AsPrimitive, vec2, AbsDiffEq, BaseFloat, InnerSpace, Matrix4, One, Vector2,
+};
+use cgmath::{Vector3, Zero};
 use log::info;
-use wgpu::SurfaceConfiguration;
-use winit::dpi::PhysicalSize;
 
 pub mod euclidian;
 pub mod hyperbolic;
+pub mod spatial;
+pub mod spherical;
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum Geometry {
+    Euclidian,
+    Hyperbolic,
+    Spherical,
+}
+
+impl Geometry {
+    pub fn next(self) -> Geometry {
+        match self {
+            Geometry::Euclidian => Geometry::Hyperbolic,
+            Geometry::Hyperbolic => Geometry::Spherical,
+            Geometry::Spherical => Geometry::Euclidian,
+        }
+    }
+
+    // (edge_count, sides, around_vertex) of the board used when nothing else is picked
+    pub fn default_tiling(self) -> (u32, u32, u32) {
+        match self {
+            Geometry::Euclidian => (19, 4, 4),
+            Geometry::Hyperbolic => (5, 5, 4),
+            // dodecahedron, enough rings to reach the far side
+            Geometry::Spherical => (11, 5, 3),
+        }
+    }
+
+    // geometry a regular {sides, around_vertex} tiling lives in
+    pub fn of_tiling(sides: u32, around_vertex: u32) -> Option<Geometry> {
+        if sides < 3 || around_vertex < 3 {
+            return None;
+        }
+        match ((sides - 2) * (around_vertex - 2)).cmp(&4) {
+            std::cmp::Ordering::Less => Some(Geometry::Spherical),
+            std::cmp::Ordering::Equal => Some(Geometry::Euclidian),
+            std::cmp::Ordering::Greater => Some(Geometry::Hyperbolic),
+        }
+    }
+}
+
+// solves from_screen's flat = v / base for v, shared by the curved geometries
+fn flat_to_screen(flat: Vector2<f64>, projection_factor: f64) -> Vector2<f64> {
+    let pf = projection_factor;
+    let root = (1.0 - pf * (2.0 - pf) * flat.magnitude2()).max(0.0).sqrt();
+    flat * ((2.0 - pf) / (1.0 + root))
+}
 
 pub trait Point: Copy + Clone + Debug + Display + PartialEq + AbsDiffEq
 // + ops::Mul<f64, Output = Self>
@@ -19,6 +68,18 @@ pub trait Point: Copy + Clone + Debug + Display + PartialEq + AbsDiffEq
     fn zero() -> Self;
     fn from_flat(x: f64, y: f64) -> Self;
     fn from_projective(x: f64, y: f64, w: f64) -> Self;
+    // inverse of the outer shader's projection, v in [-1, 1] screen space,
+    // also returns whether v was outside the projectable area and got clipped
+    fn from_screen(v: Vector2<f64>, projection_factor: f64) -> (Self, bool);
+    // the other way around, None where the point isn't shown
+    fn to_screen(&self, projection_factor: f64) -> Option<Vector2<f64>>;
+
+    // coordinates in a conformal chart (poincare disk, stereographic) where balls
+    // stay round, for spatial indexing, None where the chart breaks down
+    fn to_chart(&self) -> Option<Vector2<f64>>;
+    // (center, radius) of a chart disk covering the ball of this radius around self,
+    // None if it's unbounded
+    fn chart_ball(&self, radius: f64) -> Option<(Vector2<f64>, f64)>;
 
     fn angle(&self) -> f64;
     /*     fn flat_magnitude(&self) -> f64; */
@@ -38,6 +99,8 @@ pub trait Spinor:
 {
     type Point: Point;
 
+    fn geometry() -> Geometry;
+
     fn new(s: f64, xy: f64, yw: f64, wx: f64) -> Self;
     fn translation(amt: f64, angle: f64) -> Self;
     fn translation_to(v: Self::Point) -> Self;
@@ -55,6 +118,19 @@ pub trait Spinor:
     // TODO doesn't really fit here
     fn tiling_get_distance(sides: u32, angle: f64) -> f64;
     fn distance_to_flat(d: f64) -> f64;
+    // TODO find a value here that keeps stones in better scale
+    fn stone_scale(distance: f64) -> f64;
+
+    // projection_factor is a zoom for euclidian, poincare factor for hyperbolic
+    fn adjust_projection_factor(projection_factor: f64, amt: f64) -> f64;
+    fn camera_w_scale(projection_factor: f64) -> f64;
+    // whether the outer shader can skip reprojecting the render target
+    fn skip_reprojection() -> bool;
+    // normalize and project straight down instead of dividing by w, hiding
+    // everything behind the w = 0 plane
+    fn project_orthographic() -> bool {
+        false
+    }
 
     fn magnitude(&self) -> f64 {
         self.magnitude2().sqrt()
@@ -65,6 +141,98 @@ pub trait Spinor:
     }
 }
 
+// what the middle of the board sits on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoardCenter {
+    Vertex,
+    Edge,
+    Face,
+}
+
+impl BoardCenter {
+    // odd sizes have a center point, even ones a center face
+    pub fn for_edge_count(edge_count: u32) -> BoardCenter {
+        if edge_count % 2 == 1 {
+            BoardCenter::Vertex
+        } else {
+            BoardCenter::Face
+        }
+    }
+}
+
+impl Display for BoardCenter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BoardCenter::Vertex => "vertex",
+            BoardCenter::Edge => "edge",
+            BoardCenter::Face => "face",
+        })
+    }
+}
+
+impl FromStr for BoardCenter {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertex" => Ok(BoardCenter::Vertex),
+            "edge" => Ok(BoardCenter::Edge),
+            "face" => Ok(BoardCenter::Face),
+            _ => Err(()),
+        }
+    }
+}
+
+// which points of the tiling around the center make it onto the board
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Boundary {
+    // (edge_count - 1) / 2 rings of whole faces, an edge_count square on {4,4}
+    Rings,
+    // every point within this distance of the center
+    Radius(f64),
+    // every point within this many links of the center's points
+    GraphDistance(u32),
+    // this many points closest to the center
+    PointCount(u32),
+}
+
+// written as rule:value, e.g. points:81
+impl Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Boundary::Rings => write!(f, "rings"),
+            Boundary::Radius(r) => write!(f, "radius:{}", r),
+            Boundary::GraphDistance(d) => write!(f, "graph:{}", d),
+            Boundary::PointCount(n) => write!(f, "points:{}", n),
+        }
+    }
+}
+
+impl FromStr for Boundary {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, value) = s.split_once(':').unwrap_or((s, ""));
+        match rule {
+            "rings" if value.is_empty() => Ok(Boundary::Rings),
+            "radius" => value
+                .parse()
+                .ok()
+                .filter(|r: &f64| *r >= 0.0)
+                .map(Boundary::Radius)
+                .ok_or(()),
+            "graph" => value.parse().map(Boundary::GraphDistance).map_err(|_| ()),
+            "points" => value
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .map(Boundary::PointCount)
+                .ok_or(()),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct TilingParameters {
     // n for an n*n square board
@@ -76,8 +244,10 @@ pub struct TilingParameters {
     pub distance: f64,
 
     // in flat coordinates
-    pub link_len: f64,
     pub stone_scale: f64,
+
+    pub center: BoardCenter,
+    pub boundary: Boundary,
 }
 
 impl TilingParameters {
@@ -88,24 +258,28 @@ impl TilingParameters {
     ) -> TilingParameters {
         let angle = 2.0 * PI / (around_vertex as f64);
         let distance = SpinorT::tiling_get_distance(sides, angle);
-        let link_len = SpinorT::distance_to_flat(distance);
-        // TODO find a value here that keeps stones in better scale
-        let stone_scale = SpinorT::distance_to_flat(distance)
-            * if cfg!(feature = "euclidian_geometry") {
-                1.0
-            } else {
-                1.5
-            };
+        let stone_scale = SpinorT::stone_scale(distance);
         Self {
             edge_count,
             sides,
             around_vertex,
             angle,
             distance,
-            link_len,
             stone_scale,
+            center: BoardCenter::for_edge_count(edge_count),
+            boundary: Boundary::Rings,
         }
     }
+
+    pub fn with_center(mut self, center: BoardCenter) -> Self {
+        self.center = center;
+        self
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
 }
 
 pub struct ViewState<SpinorT: Spinor> {
@@ -118,7 +292,12 @@ pub struct ViewState<SpinorT: Spinor> {
     pub floating_origin: SpinorT,
 }
 
-// TODO lots of cfg! here, break some of it out into trait impls?
+impl<SpinorT: Spinor> Default for ViewState<SpinorT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<SpinorT: Spinor> ViewState<SpinorT> {
     pub fn new() -> Self {
         Self {
@@ -133,43 +312,45 @@ impl<SpinorT: Spinor> ViewState<SpinorT> {
     // TODO maybe store w/h in here?
     pub fn pixel_to_world_coords(
         &self,
-        size: PhysicalSize<u32>,
+        width: u32,
+        height: u32,
         x: f64,
         y: f64,
     ) -> (SpinorT::Point, bool) {
         let v = vec2(
-            (2.0 * x / size.width as f64 - 1.0) / self.w_scale,
-            (-2.0 * y / size.height as f64 + 1.0) / self.h_scale,
+            (2.0 * x / width as f64 - 1.0) / self.w_scale,
+            (-2.0 * y / height as f64 + 1.0) / self.h_scale,
         );
-        let mut clipped = false;
-        let adjusted = if cfg!(feature = "euclidian_geometry") {
-            (1.0 / self.projection_factor) * v
-        } else {
-            const LIMIT: f64 = 0.99;
-            let mag2 = v.magnitude2();
-            let limited = if mag2 < LIMIT {
-                v
-            } else {
-                clipped = true;
-                v * (LIMIT / mag2).sqrt()
-            };
-            let base = (0.5 * (1.0 + mag2.min(LIMIT))) * self.projection_factor + 1.0
-                - self.projection_factor;
-            limited / base
-        };
+        let (pos, clipped) = SpinorT::Point::from_screen(v, self.projection_factor);
+        (self.camera.apply(pos), clipped)
+    }
 
-        (
-            self.camera.apply(SpinorT::Point::from_flat_vec(adjusted)),
-            clipped,
-        )
+    // where a transform relative to the floating origin ends up in pixels, along with
+    // how many pixels a unit of distance covers there
+    pub fn world_to_pixel_coords(
+        &self,
+        width: u32,
+        height: u32,
+        relative_transform: SpinorT,
+    ) -> Option<(Vector2<f64>, f64)> {
+        const STEP: f64 = 1e-3;
+        let transform = self.camera.reverse() * self.floating_origin * relative_transform;
+        let to_pixels = |transform: SpinorT| {
+            let v = transform
+                .apply(SpinorT::Point::zero())
+                .to_screen(self.projection_factor)?;
+            Some(vec2(
+                (v.x * self.w_scale + 1.0) * 0.5 * width as f64,
+                (1.0 - v.y * self.h_scale) * 0.5 * height as f64,
+            ))
+        };
+        let pixel = to_pixels(transform)?;
+        let step = to_pixels(transform * SpinorT::translation(STEP, 0.0))?;
+        Some((pixel, (step - pixel).magnitude() / STEP))
     }
 
     pub fn adjust_projection_factor(&mut self, amt: f64) {
-        if cfg!(feature = "euclidian_geometry") {
-            self.projection_factor *= amt + 1.0;
-        } else {
-            self.projection_factor = (self.projection_factor + amt).clamp(0.0, 1.0);
-        }
+        self.projection_factor = SpinorT::adjust_projection_factor(self.projection_factor, amt);
     }
 
     pub fn reset_camera(&mut self) {
@@ -209,9 +390,7 @@ impl<SpinorT: Spinor> ViewState<SpinorT> {
 
     pub fn get_camera_mat(&self) -> Matrix4<f32> {
         let mut scale_mat = Matrix4::<f32>::one();
-        if cfg!(feature = "euclidian_geometry") {
-            scale_mat.w.w = 1.0 / self.projection_factor as f32;
-        }
+        scale_mat.w.w = SpinorT::camera_w_scale(self.projection_factor) as f32;
 
         scale_mat * (self.camera.reverse() * self.floating_origin).into_mat4()
     }
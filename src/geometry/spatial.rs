@@ -0,0 +1,225 @@
+use cgmath::{vec2, Vector2};
+
+use super::*;
+
+const LEAF_CAPACITY: usize = 8;
+const MIN_HALF_SIZE: f64 = 1e-9;
+
+struct QuadNode<PointT: Point> {
+    center: Vector2<f64>,
+    half_size: f64,
+    // quadrants, indexed by (x >= center.x) + 2 * (y >= center.y)
+    children: Option<[usize; 4]>,
+    entries: Vec<(Vector2<f64>, PointT, i32)>,
+}
+
+impl<PointT: Point> QuadNode<PointT> {
+    fn new(center: Vector2<f64>, half_size: f64) -> Self {
+        Self {
+            center,
+            half_size,
+            children: None,
+            entries: Vec::new(),
+        }
+    }
+
+    fn contains(&self, v: Vector2<f64>) -> bool {
+        (v.x - self.center.x).abs() <= self.half_size
+            && (v.y - self.center.y).abs() <= self.half_size
+    }
+
+    fn quadrant(&self, v: Vector2<f64>) -> usize {
+        (v.x >= self.center.x) as usize + 2 * (v.y >= self.center.y) as usize
+    }
+
+    fn intersects_disk(&self, center: Vector2<f64>, radius: f64) -> bool {
+        let dx = ((center.x - self.center.x).abs() - self.half_size).max(0.0);
+        let dy = ((center.y - self.center.y).abs() - self.half_size).max(0.0);
+        dx * dx + dy * dy <= radius * radius
+    }
+}
+
+// quadtree over Point::to_chart coordinates, so lookups only need to look at
+// points near the chart disk of the query ball
+pub struct PointIndex<PointT: Point> {
+    nodes: Vec<QuadNode<PointT>>,
+    root: usize,
+    // points the chart can't represent, always checked
+    unmapped: Vec<(PointT, i32)>,
+}
+
+impl<PointT: Point> Default for PointIndex<PointT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<PointT: Point> PointIndex<PointT> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![QuadNode::new(vec2(0.0, 0.0), 1.0)],
+            root: 0,
+            unmapped: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, pos: PointT, idx: i32) {
+        let Some(v) = pos
+            .to_chart()
+            .filter(|v| v.x.is_finite() && v.y.is_finite())
+        else {
+            self.unmapped.push((pos, idx));
+            return;
+        };
+
+        // grow outwards, the old root becomes one quadrant of the new one
+        while !self.nodes[self.root].contains(v) {
+            let old = &self.nodes[self.root];
+            let half_size = old.half_size;
+            let dir = vec2(
+                if v.x >= old.center.x { 1.0 } else { -1.0 },
+                if v.y >= old.center.y { 1.0 } else { -1.0 },
+            );
+            let mut new_root = QuadNode::new(old.center + dir * half_size, 2.0 * half_size);
+            let old_quadrant = new_root.quadrant(old.center);
+            let mut children = [0; 4];
+            for (q, child) in children.iter_mut().enumerate() {
+                *child = if q == old_quadrant {
+                    self.root
+                } else {
+                    self.nodes.push(QuadNode::new(
+                        Self::quadrant_center(&new_root, q),
+                        half_size,
+                    ));
+                    self.nodes.len() - 1
+                };
+            }
+            new_root.children = Some(children);
+            self.nodes.push(new_root);
+            self.root = self.nodes.len() - 1;
+        }
+
+        let mut node = self.root;
+        while let Some(children) = self.nodes[node].children {
+            node = children[self.nodes[node].quadrant(v)];
+        }
+        self.nodes[node].entries.push((v, pos, idx));
+        if self.nodes[node].entries.len() > LEAF_CAPACITY
+            && self.nodes[node].half_size > MIN_HALF_SIZE
+        {
+            self.split(node);
+        }
+    }
+
+    fn quadrant_center(node: &QuadNode<PointT>, quadrant: usize) -> Vector2<f64> {
+        let offset = 0.5 * node.half_size;
+        node.center
+            + vec2(
+                if quadrant & 1 == 1 { offset } else { -offset },
+                if quadrant & 2 == 2 { offset } else { -offset },
+            )
+    }
+
+    fn split(&mut self, node: usize) {
+        let first_child = self.nodes.len();
+        for q in 0..4 {
+            let center = Self::quadrant_center(&self.nodes[node], q);
+            let half_size = 0.5 * self.nodes[node].half_size;
+            self.nodes.push(QuadNode::new(center, half_size));
+        }
+        let entries = std::mem::take(&mut self.nodes[node].entries);
+        for entry in entries {
+            let q = self.nodes[node].quadrant(entry.0);
+            self.nodes[first_child + q].entries.push(entry);
+        }
+        self.nodes[node].children = Some([
+            first_child,
+            first_child + 1,
+            first_child + 2,
+            first_child + 3,
+        ]);
+    }
+
+    // lowest index within dist of pos, or -1, same as a linear scan would give
+    pub fn find(&self, pos: PointT, dist: f64) -> i32 {
+        let mut found = -1;
+        let mut check = |p: &PointT, idx: i32| {
+            if (found < 0 || idx < found) && pos.distance(*p) <= dist {
+                found = idx;
+            }
+        };
+        for (p, idx) in &self.unmapped {
+            check(p, *idx);
+        }
+
+        // a little slack for rounding in the chart
+        let disk = pos
+            .chart_ball(dist)
+            .filter(|(c, r)| c.x.is_finite() && c.y.is_finite() && r.is_finite())
+            .map(|(c, r)| (c, r * (1.0 + 1e-9) + 1e-12));
+        let mut stack = vec![self.root];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if let Some((center, radius)) = disk {
+                if !node.intersects_disk(center, radius) {
+                    continue;
+                }
+            }
+            match node.children {
+                Some(children) => stack.extend(children),
+                None => {
+                    for (_, p, idx) in &node.entries {
+                        check(p, *idx);
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::hyperbolic::PointHyperbolic;
+    use crate::geometry::spherical::PointSpherical;
+
+    fn check_against_scan<PointT: Point>(points: &[PointT], dist: f64) {
+        let mut index = PointIndex::new();
+        for (i, &p) in points.iter().enumerate() {
+            index.insert(p, i as i32);
+        }
+        for &query in points {
+            let scan = points
+                .iter()
+                .position(|p| query.distance(*p) <= dist)
+                .map_or(-1, |i| i as i32);
+            assert_eq!(index.find(query, dist), scan);
+        }
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        // deterministic spread of points out towards the edge of the disk
+        let flat: Vec<Vector2<f64>> = (0..500)
+            .map(|i| {
+                let r = 0.999 * ((i * 7919) % 500) as f64 / 500.0;
+                let angle = i as f64 * 2.399;
+                vec2(r * angle.cos(), r * angle.sin())
+            })
+            .collect();
+        let hyperbolic: Vec<PointHyperbolic> = flat
+            .iter()
+            .map(|&v| PointHyperbolic::from_flat_vec(v))
+            .collect();
+        let spherical: Vec<PointSpherical> = flat
+            .iter()
+            .map(|&v| PointSpherical::from_flat_vec(v * 3.0))
+            .chain([PointSpherical::from_projective(0.0, 0.0, -1.0)])
+            .collect();
+        for dist in [1e-3, 0.1, 0.5, 2.0] {
+            check_against_scan(&hyperbolic, dist);
+            check_against_scan(&spherical, dist);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+use std::{f64::consts::PI, ops};
+
+use cgmath::{assert_abs_diff_eq, vec2, vec3, BaseFloat, InnerSpace, Matrix4, Vector2, Vector3};
+
+use super::*;
+
+// point on the unit sphere, the board origin is the pole at w = 1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PointSpherical {
+    x: f64,
+    y: f64,
+    w: f64,
+}
+
+impl Point for PointSpherical {
+    fn distance(self, b: Self) -> f64 {
+        let d = self.w * b.w + self.x * b.x + self.y * b.y;
+        d.clamp(-1.0, 1.0).acos()
+    }
+
+    fn zero() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            w: 1.0,
+        }
+    }
+
+    // gnomonic projection, only reaches the hemisphere around the origin
+    fn from_flat(x: f64, y: f64) -> Self {
+        let w = (1.0 / (1.0 + x * x + y * y)).sqrt();
+        Self {
+            x: x * w,
+            y: y * w,
+            w,
+        }
+    }
+
+    fn from_projective(x: f64, y: f64, w: f64) -> Self {
+        assert_abs_diff_eq!(x * x + y * y + w * w, 1.0, epsilon = 1e-9);
+        Self { x, y, w }
+    }
+
+    // the render target holds an orthographic view of the front hemisphere
+    fn from_screen(v: Vector2<f64>, projection_factor: f64) -> (Self, bool) {
+        const LIMIT: f64 = 0.99;
+        let mag2 = v.magnitude2();
+        let clipped = mag2 >= LIMIT;
+        let limited = if clipped {
+            v * (LIMIT / mag2).sqrt()
+        } else {
+            v
+        };
+        let base = (0.5 * (1.0 + mag2.min(LIMIT))) * projection_factor + 1.0 - projection_factor;
+        let flat = limited / base;
+        let w = (1.0 - flat.magnitude2()).max(0.0).sqrt();
+        (
+            Self {
+                x: flat.x,
+                y: flat.y,
+                w,
+            },
+            clipped,
+        )
+    }
+
+    // the back hemisphere is hidden
+    fn to_screen(&self, projection_factor: f64) -> Option<Vector2<f64>> {
+        (self.w >= 0.0).then(|| flat_to_screen(vec2(self.x, self.y), projection_factor))
+    }
+
+    // stereographic from the antipode of the origin
+    fn to_chart(&self) -> Option<Vector2<f64>> {
+        (self.w > -1.0 + 1e-9).then(|| vec2(self.x, self.y) / (1.0 + self.w))
+    }
+
+    fn chart_ball(&self, radius: f64) -> Option<(Vector2<f64>, f64)> {
+        let theta = self.w.clamp(-1.0, 1.0).acos();
+        if theta + radius >= PI - 1e-9 {
+            return None;
+        }
+        let v = vec2(self.x, self.y);
+        if v.magnitude2() < 1e-24 {
+            return Some((vec2(0.0, 0.0), (0.5 * radius).tan()));
+        }
+        let r_in = (0.5 * (theta - radius)).tan();
+        let r_out = (0.5 * (theta + radius)).tan();
+        Some((v.normalize() * (0.5 * (r_in + r_out)), 0.5 * (r_out - r_in)))
+    }
+
+    fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    fn to_projective<S: 'static + BaseFloat>(&self) -> Vector3<S>
+    where
+        f32: AsPrimitive<S>,
+        f64: AsPrimitive<S>,
+    {
+        vec3(self.x.as_(), self.y.as_(), self.w.as_())
+    }
+}
+
+impl AbsDiffEq for PointSpherical {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> Self::Epsilon {
+        1e-9
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        f64::abs_diff_eq(&self.x, &other.x, epsilon)
+            && f64::abs_diff_eq(&self.y, &other.y, epsilon)
+            && f64::abs_diff_eq(&self.w, &other.w, epsilon)
+    }
+}
+
+impl Display for PointSpherical {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(3);
+        write!(
+            f,
+            "[{:.*?}, {:.*?}, {:.*?}]",
+            precision, self.x, precision, self.y, precision, self.w
+        )
+    }
+}
+
+// rotor of the sphere, same layout as the other spinors but every bivector squares to -1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpinorSpherical {
+    s: f64,
+    xy: f64,
+    yw: f64,
+    wx: f64,
+}
+
+impl Spinor for SpinorSpherical {
+    type Point = PointSpherical;
+
+    fn geometry() -> Geometry {
+        Geometry::Spherical
+    }
+
+    fn new(s: f64, xy: f64, yw: f64, wx: f64) -> Self {
+        Self { s, xy, yw, wx }
+    }
+
+    fn apply(&self, v: Self::Point) -> Self::Point {
+        Self::Point {
+            x: (self.s * self.s + self.yw * self.yw - self.wx * self.wx - self.xy * self.xy) * v.x
+                + (2.0 * self.s * self.xy + 2.0 * self.yw * self.wx) * v.y
+                + (-2.0 * self.s * self.wx + 2.0 * self.yw * self.xy) * v.w,
+            y: (-2.0 * self.s * self.xy + 2.0 * self.yw * self.wx) * v.x
+                + (self.s * self.s - self.yw * self.yw + self.wx * self.wx - self.xy * self.xy)
+                    * v.y
+                + (2.0 * self.s * self.yw + 2.0 * self.wx * self.xy) * v.w,
+            w: (2.0 * self.s * self.wx + 2.0 * self.yw * self.xy) * v.x
+                + (-2.0 * self.s * self.yw + 2.0 * self.wx * self.xy) * v.y
+                + (self.s * self.s - self.yw * self.yw - self.wx * self.wx + self.xy * self.xy)
+                    * v.w,
+        }
+    }
+
+    fn reverse(&self) -> Self {
+        Self {
+            s: self.s,
+            xy: -self.xy,
+            yw: -self.yw,
+            wx: -self.wx,
+        }
+    }
+
+    fn magnitude2(&self) -> f64 {
+        self.s * self.s + self.xy * self.xy + self.yw * self.yw + self.wx * self.wx
+    }
+
+    fn distance(self, b: Self) -> f64 {
+        let p = self.apply(Point::zero());
+        let q = b.apply(Point::zero());
+        p.distance(q)
+    }
+
+    fn into_mat4<S: 'static + BaseFloat>(&self) -> Matrix4<S>
+    where
+        f32: AsPrimitive<S>,
+        f64: AsPrimitive<S>,
+    {
+        // same as apply, with an empty z row and column
+        Matrix4::new(
+            (self.s * self.s + self.yw * self.yw - self.wx * self.wx - self.xy * self.xy).as_(),
+            (-2.0 * self.s * self.xy + 2.0 * self.yw * self.wx).as_(),
+            0.0.as_(),
+            (2.0 * self.s * self.wx + 2.0 * self.yw * self.xy).as_(),
+            (2.0 * self.s * self.xy + 2.0 * self.yw * self.wx).as_(),
+            (self.s * self.s - self.yw * self.yw + self.wx * self.wx - self.xy * self.xy).as_(),
+            0.0.as_(),
+            (-2.0 * self.s * self.yw + 2.0 * self.wx * self.xy).as_(),
+            0.0.as_(),
+            0.0.as_(),
+            0.0.as_(),
+            0.0.as_(),
+            (-2.0 * self.s * self.wx + 2.0 * self.yw * self.xy).as_(),
+            (2.0 * self.s * self.yw + 2.0 * self.wx * self.xy).as_(),
+            0.0.as_(),
+            (self.s * self.s - self.yw * self.yw - self.wx * self.wx + self.xy * self.xy).as_(),
+        )
+    }
+
+    fn translation(amt: f64, angle: f64) -> Self {
+        let b2 = amt / 2.0;
+        Self {
+            s: b2.cos(),
+            xy: 0.0,
+            yw: angle.cos() * b2.sin(),
+            wx: angle.sin() * b2.sin(),
+        }
+    }
+
+    // undefined for the antipode of the origin
+    fn translation_to(v: Self::Point) -> Self {
+        let w_factor = (2.0 * (v.w + 1.0)).sqrt();
+
+        Self {
+            s: (0.5 * (v.w + 1.0)).sqrt(),
+            xy: 0.0,
+            yw: v.y / w_factor,
+            wx: -v.x / w_factor,
+        }
+    }
+
+    fn rotation(angle: f64) -> Self {
+        let t2 = angle / 2.0;
+        Self {
+            s: t2.cos(),
+            xy: t2.sin(),
+            yw: 0.0,
+            wx: 0.0,
+        }
+    }
+
+    // only defined for 1/p + 1/q > 1/2, the platonic solids
+    fn tiling_get_distance(sides: u32, angle: f64) -> f64 {
+        2.0 * ((PI / (sides as f64)).cos() / (0.5 * angle).sin()).acos()
+    }
+    fn distance_to_flat(d: f64) -> f64 {
+        d.tan()
+    }
+
+    // tan blows up on the larger solids, stay with the angle
+    fn stone_scale(distance: f64) -> f64 {
+        distance
+    }
+
+    fn adjust_projection_factor(projection_factor: f64, amt: f64) -> f64 {
+        (projection_factor + amt).clamp(0.0, 1.0)
+    }
+
+    fn camera_w_scale(_projection_factor: f64) -> f64 {
+        1.0
+    }
+
+    fn skip_reprojection() -> bool {
+        false
+    }
+
+    fn project_orthographic() -> bool {
+        true
+    }
+}
+
+impl One for SpinorSpherical {
+    fn one() -> Self {
+        Self {
+            s: 1.0,
+            xy: 0.0,
+            yw: 0.0,
+            wx: 0.0,
+        }
+    }
+}
+
+impl ops::Mul<SpinorSpherical> for SpinorSpherical {
+    type Output = SpinorSpherical;
+
+    fn mul(self, rhs: SpinorSpherical) -> SpinorSpherical {
+        SpinorSpherical {
+            s: self.s * rhs.s - self.xy * rhs.xy - self.yw * rhs.yw - self.wx * rhs.wx,
+            xy: self.s * rhs.xy + self.xy * rhs.s - self.yw * rhs.wx + self.wx * rhs.yw,
+            yw: self.s * rhs.yw + self.yw * rhs.s - self.wx * rhs.xy + self.xy * rhs.wx,
+            wx: self.s * rhs.wx + self.wx * rhs.s - self.xy * rhs.yw + self.yw * rhs.xy,
+        }
+    }
+}
+impl ops::Mul<f64> for SpinorSpherical {
+    type Output = SpinorSpherical;
+
+    fn mul(self, rhs: f64) -> SpinorSpherical {
+        SpinorSpherical {
+            s: rhs * self.s,
+            xy: rhs * self.xy,
+            yw: rhs * self.yw,
+            wx: rhs * self.wx,
+        }
+    }
+}
+
+impl AbsDiffEq for SpinorSpherical {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> Self::Epsilon {
+        1e-9
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        f64::abs_diff_eq(&self.s, &other.s, epsilon)
+            && f64::abs_diff_eq(&self.xy, &other.xy, epsilon)
+            && f64::abs_diff_eq(&self.yw, &other.yw, epsilon)
+            && f64::abs_diff_eq(&self.wx, &other.wx, epsilon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translation_to() {
+        let v = PointSpherical::from_flat(0.7, -0.4);
+        let s = SpinorSpherical::translation_to(v);
+        assert_abs_diff_eq!(s.apply(PointSpherical::zero()), v, epsilon = 1e-9);
+        assert_abs_diff_eq!(s.reverse().apply(v), PointSpherical::zero(), epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_composition() {
+        let a = SpinorSpherical::translation(0.8, 0.3) * SpinorSpherical::rotation(1.1);
+        let b = SpinorSpherical::translation(1.9, -2.0);
+        let v = PointSpherical::from_flat(0.2, 0.5);
+        assert_abs_diff_eq!((a * b).apply(v), a.apply(b.apply(v)), epsilon = 1e-9);
+        assert_abs_diff_eq!(
+            SpinorSpherical::translation(PI, 0.0).apply(PointSpherical::zero()),
+            PointSpherical::from_projective(0.0, 0.0, -1.0),
+            epsilon = 1e-9
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use std::io::{BufRead, Write};
+
+use log::info;
+
+use crate::game::search::*;
+use crate::game::*;
+use crate::geometry::*;
+
+// gtp skips the letter I in column names
+const GTP_COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+const KNOWN_COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "fixed_handicap",
+    "place_free_handicap",
+    "set_free_handicap",
+    "play",
+    "genmove",
+    "undo",
+    "final_score",
+    "loadsgf",
+    "printsgf",
+    "hypergo-tiling",
+    "hypergo-boundary",
+    "hypergo-point_count",
+];
+
+struct GtpEngine<SpinorT: Spinor> {
+    game: GameState<SpinorT>,
+    edge_count: u32,
+    sides: u32,
+    around_vertex: u32,
+    boundary: Boundary,
+    // None to go by the parity of edge_count
+    center: Option<BoardCenter>,
+    komi: f64,
+    search_params: SearchParams,
+    rng_state: u64,
+}
+
+impl<SpinorT: Spinor> GtpEngine<SpinorT> {
+    fn new(edge_count: u32, sides: u32, around_vertex: u32, search_params: SearchParams) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        let mut engine = Self {
+            game: GameState::new(
+                TilingParameters::new::<SpinorT>(edge_count, sides, around_vertex),
+                0,
+            ),
+            edge_count,
+            sides,
+            around_vertex,
+            boundary: Boundary::Rings,
+            center: None,
+            komi: 0.0,
+            search_params,
+            rng_state: seed | 1,
+        };
+        engine.game.komi = engine.komi;
+        engine
+    }
+
+    fn tiling_parameters(&self) -> TilingParameters {
+        let params =
+            TilingParameters::new::<SpinorT>(self.edge_count, self.sides, self.around_vertex)
+                .with_boundary(self.boundary);
+        let center = self.center.unwrap_or(params.center);
+        params.with_center(center)
+    }
+
+    // komi carries over, it is only changed by the komi command or loading a record
+    fn clear_board(&mut self) {
+        self.game = GameState::new(self.tiling_parameters(), 0);
+        self.game.komi = self.komi;
+    }
+
+    fn check_empty_board(&self) -> Result<(), String> {
+        if self.game.moves().is_empty() && self.game.board().setup_stones().is_empty() {
+            Ok(())
+        } else {
+            Err("board not empty".into())
+        }
+    }
+
+    // xorshift64
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        self.rng_state
+    }
+
+    fn parse_color(arg: Option<&str>) -> Result<Turn, String> {
+        match arg.map(|a| a.to_ascii_lowercase()).as_deref() {
+            Some("b") | Some("black") => Ok(Turn::Black),
+            Some("w") | Some("white") => Ok(Turn::White),
+            _ => Err("invalid color".into()),
+        }
+    }
+
+    // None for pass
+    fn parse_vertex(&self, arg: Option<&str>) -> Result<Option<i32>, String> {
+        let arg = arg.ok_or("missing vertex")?.to_ascii_uppercase();
+        if arg == "PASS" {
+            return Ok(None);
+        }
+        let idx = if self.game.is_square_grid() {
+            let col = GTP_COLUMNS
+                .iter()
+                .position(|&c| c == arg.as_bytes()[0])
+                .ok_or("invalid vertex")? as u32;
+            let number = arg[1..].parse::<u32>().map_err(|_| "invalid vertex")?;
+            if number == 0 || number > self.edge_count {
+                return Err("invalid vertex".into());
+            }
+            self.game.point_at_grid(col, self.edge_count - number)
+        } else {
+            arg.parse()
+                .ok()
+                .and_then(|name| self.game.point_by_name(name))
+        };
+        idx.map(Some).ok_or("invalid vertex".into())
+    }
+
+    fn format_vertex(&self, idx: i32) -> String {
+        match self.game.grid_coords(idx) {
+            Some((col, row)) => format!(
+                "{}{}",
+                GTP_COLUMNS[col as usize] as char,
+                self.edge_count - row
+            ),
+            None => self.game.point_name(idx).to_string(),
+        }
+    }
+
+    // plays the search's pick, None when it passes
+    fn generate_move(&mut self) -> Option<i32> {
+        let seed = self.next_random();
+        match search(&self.game, &self.search_params, seed).best {
+            Move::Place(idx) if self.game.play_point(idx) => Some(idx),
+            _ => None,
+        }
+    }
+
+    fn set_tiling(&mut self, sides: u32, around_vertex: u32) -> Result<(), String> {
+        if Geometry::of_tiling(sides, around_vertex) != Some(SpinorT::geometry()) {
+            return Err(format!(
+                "{{{},{}}} is not a {:?} tiling",
+                sides,
+                around_vertex,
+                SpinorT::geometry()
+            ));
+        }
+        self.sides = sides;
+        self.around_vertex = around_vertex;
+        self.clear_board();
+        Ok(())
+    }
+
+    fn handle(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match command {
+            "protocol_version" => Ok("2".into()),
+            "name" => Ok("hypergo".into()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").into()),
+            "known_command" => Ok(KNOWN_COMMANDS
+                .contains(args.first().unwrap_or(&""))
+                .to_string()),
+            "list_commands" => Ok(KNOWN_COMMANDS.join("\n")),
+            "quit" => Ok("".into()),
+            "boardsize" => {
+                let size = args
+                    .first()
+                    .and_then(|a| a.parse::<u32>().ok())
+                    .ok_or("boardsize not an integer")?;
+                // square grids are addressed by letter, and run out of them past 25
+                let square = SpinorT::geometry() == Geometry::Euclidian
+                    && (self.sides, self.around_vertex) == (4, 4)
+                    && self.boundary == Boundary::Rings
+                    && self.center.is_none();
+                if size < 1 || (square && size as usize > GTP_COLUMNS.len()) {
+                    return Err("unacceptable size".into());
+                }
+                self.edge_count = size;
+                self.clear_board();
+                Ok("".into())
+            }
+            "clear_board" => {
+                self.clear_board();
+                Ok("".into())
+            }
+            "komi" => {
+                self.komi = args
+                    .first()
+                    .and_then(|a| a.parse::<f64>().ok())
+                    .ok_or("komi not a float")?;
+                self.game.komi = self.komi;
+                Ok("".into())
+            }
+            // we have no fixed star points, so the engine's choice is the same either way
+            "fixed_handicap" | "place_free_handicap" => {
+                let count = args
+                    .first()
+                    .and_then(|a| a.parse::<u32>().ok())
+                    .filter(|&n| n >= 2 && (n as usize) < self.game.point_count())
+                    .ok_or("invalid number of stones")?;
+                self.check_empty_board()?;
+                self.game = GameState::new(self.tiling_parameters(), count);
+                self.game.komi = self.komi;
+                Ok(self
+                    .game
+                    .board()
+                    .setup_stones()
+                    .iter()
+                    .map(|&idx| self.format_vertex(idx))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            }
+            "set_free_handicap" => {
+                self.check_empty_board()?;
+                if args.len() < 2 {
+                    return Err("bad vertex list".into());
+                }
+                let mut game =
+                    GameState::with_free_handicap(self.tiling_parameters(), args.len() as u32);
+                for &arg in args {
+                    match self.parse_vertex(Some(arg)) {
+                        Ok(Some(idx)) if game.place_handicap_stone(idx) => {}
+                        _ => return Err("bad vertex list".into()),
+                    }
+                }
+                game.komi = self.komi;
+                self.game = game;
+                Ok("".into())
+            }
+            "play" => {
+                let color = Self::parse_color(args.first().copied())?;
+                let vertex = self.parse_vertex(args.get(1).copied())?;
+                self.game.set_turn(color);
+                match vertex {
+                    Some(idx) => {
+                        if !self.game.play_point(idx) {
+                            return Err("illegal move".into());
+                        }
+                    }
+                    None => self.game.pass_move(),
+                }
+                Ok("".into())
+            }
+            "genmove" => {
+                let color = Self::parse_color(args.first().copied())?;
+                self.game.set_turn(color);
+                match self.generate_move() {
+                    Some(idx) => Ok(self.format_vertex(idx)),
+                    None => {
+                        self.game.pass_move();
+                        Ok("pass".into())
+                    }
+                }
+            }
+            "undo" => {
+                if self.game.last_move().is_none() {
+                    return Err("cannot undo".into());
+                }
+                self.game.move_history(-1);
+                Ok("".into())
+            }
+            "final_score" => {
+                self.game.calculate_score();
+                let margin = self.game.score.as_ref().unwrap().margin();
+                Ok(if margin > 0.0 {
+                    format!("B+{}", margin)
+                } else if margin < 0.0 {
+                    format!("W+{}", -margin)
+                } else {
+                    "0".into()
+                })
+            }
+            "loadsgf" => {
+                let path = args.first().ok_or("missing filename")?;
+                let sgf = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let game = GameState::from_sgf(&sgf).map_err(|e| e.to_string())?;
+                let params = game.tiling_parameters();
+                self.edge_count = params.edge_count;
+                self.sides = params.sides;
+                self.around_vertex = params.around_vertex;
+                self.boundary = params.boundary;
+                self.center = (params.center != BoardCenter::for_edge_count(params.edge_count))
+                    .then_some(params.center);
+                self.komi = game.komi;
+                self.game = game;
+                Ok("".into())
+            }
+            "printsgf" => Ok(self.game.to_sgf().trim_end().into()),
+            "hypergo-tiling" => {
+                let parsed: Vec<u32> = args.iter().filter_map(|a| a.parse().ok()).collect();
+                if parsed.len() != 2 || args.len() != 2 {
+                    return Err("expected sides and around_vertex".into());
+                }
+                self.set_tiling(parsed[0], parsed[1])?;
+                Ok("".into())
+            }
+            "hypergo-boundary" => {
+                let (rule, rest) = args.split_first().ok_or("missing boundary rule")?;
+                let (boundary, rest) = match (*rule, rest.split_first()) {
+                    ("rings", _) => (Ok(Boundary::Rings), rest),
+                    (_, Some((value, rest))) => {
+                        (format!("{}:{}", rule, value).parse::<Boundary>(), rest)
+                    }
+                    (_, None) => (Err(()), rest),
+                };
+                let boundary = boundary.map_err(|_| "invalid boundary")?;
+                let center = match rest.first() {
+                    Some(c) => Some(c.parse::<BoardCenter>().map_err(|_| "invalid center")?),
+                    None => None,
+                };
+                self.boundary = boundary;
+                self.center = center;
+                self.clear_board();
+                Ok("".into())
+            }
+            "hypergo-point_count" => Ok(self.game.point_count().to_string()),
+            _ => Err("unknown command".into()),
+        }
+    }
+}
+
+// speaks the go text protocol until quit or end of input
+pub fn run_gtp<SpinorT: Spinor>(
+    input: impl BufRead,
+    output: impl Write,
+    edge_count: u32,
+    sides: u32,
+    around_vertex: u32,
+    search_params: SearchParams,
+) -> std::io::Result<()> {
+    let engine = GtpEngine::<SpinorT>::new(edge_count, sides, around_vertex, search_params);
+    serve(engine, input, output)
+}
+
+fn serve<SpinorT: Spinor>(
+    mut engine: GtpEngine<SpinorT>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let id = if words[0].chars().all(|c| c.is_ascii_digit()) {
+            Some(words.remove(0))
+        } else {
+            None
+        };
+        let Some((&command, args)) = words.split_first() else {
+            continue;
+        };
+        info!("gtp command {:?} {:?}", command, args);
+
+        let (prefix, response) = match engine.handle(command, args) {
+            Ok(response) => ('=', response),
+            Err(error) => ('?', error),
+        };
+        writeln!(output, "{}{} {}\n", prefix, id.unwrap_or(""), response)?;
+        output.flush()?;
+
+        if command == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::euclidian::SpinorEuclidian;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    fn run_script<SpinorT: Spinor>(script: &str, sides: u32, around_vertex: u32) -> String {
+        let mut output = Vec::new();
+        let search_params = SearchParams {
+            playouts: 100,
+            ..Default::default()
+        };
+        let mut engine = GtpEngine::<SpinorT>::new(5, sides, around_vertex, search_params);
+        // the same moves every run
+        engine.rng_state = 1;
+        serve(engine, script.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_gtp_square() {
+        let output = run_script::<SpinorEuclidian>(
+            "boardsize 26\n1 boardsize 5\nplay b C3\nplay w C3\n2 play w D3\nkomi 0.5\nfinal_score\nundo\nprintsgf\nquit\nplay b A1\n",
+            4,
+            4,
+        );
+        assert_eq!(
+            output,
+            format!(
+                "? unacceptable size\n\n=1 \n\n= \n\n? illegal move\n\n=2 \n\n= \n\n= W+0.5\n\n= \n\n\
+                = (;FF[4]GM[1]CA[UTF-8]AP[hypergo:{}]RU[area:positional-superko:no-suicide]KM[0.5]\
+                HG[euclidian]HT[4:4:5]SZ[5];B[cc];W[dc])\n\n\
+                = \n\n",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_gtp_hyperbolic() {
+        let output = run_script::<SpinorHyperbolic>(
+            "hypergo-tiling 4 4\nhypergo-tiling 5 4\nhypergo-point_count\nplay b 2-3\ngenmove w\n\
+            hypergo-boundary points 81\nhypergo-point_count\nhypergo-boundary points\n\
+            hypergo-boundary points 0\n",
+            5,
+            4,
+        );
+        let responses: Vec<&str> = output.split("\n\n").collect();
+        assert!(responses[0].starts_with('?'));
+        assert_eq!(&responses[1..4], &["= ", "= 61", "= "]);
+        let generated = responses[4][2..].parse::<PointName>().unwrap();
+        let game = GameState::<SpinorHyperbolic>::new(
+            TilingParameters::new::<SpinorHyperbolic>(5, 5, 4),
+            0,
+        );
+        assert!(game.point_by_name(generated).is_some());
+        assert_ne!(generated.to_string(), "2-3");
+        assert_eq!(
+            &responses[5..9],
+            &["= ", "= 81", "? invalid boundary", "? invalid boundary"]
+        );
+    }
+
+    #[test]
+    fn test_gtp_komi() {
+        let mut engine = GtpEngine::<SpinorEuclidian>::new(5, 4, 4, SearchParams::default());
+        engine.handle("komi", &["0.5"]).unwrap();
+        // the search scores playouts with the game's komi
+        assert_eq!(engine.game.komi, 0.5);
+        engine.handle("clear_board", &[]).unwrap();
+        assert_eq!(engine.game.komi, 0.5);
+        engine.handle("fixed_handicap", &["2"]).unwrap();
+        assert_eq!(engine.game.komi, 0.5);
+    }
+
+    #[test]
+    fn test_gtp_handicap() {
+        let output = run_script::<SpinorEuclidian>(
+            "boardsize 9
+fixed_handicap 4
+fixed_handicap 2
+clear_board
+set_free_handicap A1 A1
+            set_free_handicap A1 J9
+play w E5
+",
+            4,
+            4,
+        );
+        let responses: Vec<&str> = output.split("\n\n").collect();
+        assert_eq!(
+            &responses[..7],
+            &[
+                "= ",
+                "= G7 C3 C7 G3",
+                "? board not empty",
+                "= ",
+                "? bad vertex list",
+                "= ",
+                "= "
+            ]
+        );
+    }
+}
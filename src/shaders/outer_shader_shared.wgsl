@@ -2,6 +2,8 @@
 var render_target_sampler: sampler;
 
 struct Uniform {
+    // 0 shows the render target as is (klein disk, or orthographic hemisphere for
+    // spherical), 1 reprojects it to the poincare disk / stereographic, same formula for both
     f: f32,
     skip_reprojection: u32,
     w_scale: f32,
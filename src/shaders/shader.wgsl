@@ -1,5 +1,6 @@
 struct Uniform {
     transform: mat4x4<f32>,
+    orthographic: u32,
 };
 @group(0) @binding(0)
 var<uniform> vertex_uniform: Uniform;
@@ -35,8 +36,16 @@ fn vs_main(
         instance.transform_3,
     );
     out.color = instance.color;
-    out.clip_position = vertex_uniform.transform * instance_transform * 
+    let pos = vertex_uniform.transform * instance_transform * 
         vec4<f32>(model.position.xy, 0.0, model.position.z);
+    if (bool(vertex_uniform.orthographic)) {
+        // spherical, keep the w > 0 hemisphere and let the outer shader
+        // turn this into a stereographic projection
+        let n = pos / length(pos);
+        out.clip_position = vec4<f32>(n.xy, 1.0 - n.w, 1.0);
+    } else {
+        out.clip_position = pos;
+    }
     // var temp_pos = instance_transform * 
     //     vec4<f32>(model.position, 1.0);
     // out.clip_position = vertex_uniform.transform * (temp_pos / temp_pos.w);
@@ -0,0 +1,234 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::game::search::SearchParams;
+use crate::game::*;
+use crate::geometry::*;
+
+// games still going after this many moves per point are scored as they stand
+const MAX_MOVES_PER_POINT: usize = 3;
+
+pub struct TournamentConfig {
+    pub games: u32,
+    pub edge_count: u32,
+    pub sides: u32,
+    pub around_vertex: u32,
+    // the first one takes black in the first game, colors swap every game
+    pub players: [Player; 2],
+    pub rules: Rules,
+    pub search_params: SearchParams,
+    // directory for the sgf records, none to skip writing them
+    pub records: Option<PathBuf>,
+    pub seed: u64,
+}
+
+// indexed like TournamentConfig::players
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TournamentStats {
+    pub games: u32,
+    pub wins: [u32; 2],
+    pub black_wins: u32,
+    pub draws: u32,
+    // summed from the first player's side
+    pub margin: f64,
+    pub moves: u32,
+}
+
+impl TournamentStats {
+    pub fn win_rate(&self, player: usize) -> f64 {
+        self.wins[player] as f64 / self.games.max(1) as f64
+    }
+
+    pub fn average_margin(&self) -> f64 {
+        self.margin / self.games.max(1) as f64
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.moves as f64 / self.games.max(1) as f64
+    }
+}
+
+// plays one game between bots, indexed by Turn, through to a final score
+pub fn play_game<SpinorT: Spinor>(
+    tiling_parameters: TilingParameters,
+    players: [Player; 2],
+    rules: Rules,
+    search_params: &SearchParams,
+    rng_state: &mut u64,
+) -> GameState<SpinorT> {
+    let mut game = GameState::new(tiling_parameters, 0);
+    game.rules = rules;
+    game.komi = rules.scoring_rule.default_komi();
+    let max_moves = MAX_MOVES_PER_POINT * game.point_count();
+    for _ in 0..max_moves {
+        if game.phase() != GamePhase::Playing {
+            break;
+        }
+        let player = players[game.turn() as usize];
+        let mv = player
+            .choose_move(&game, search_params, rng_state)
+            .unwrap_or(Move::Pass);
+        game.play_move(mv);
+    }
+    if game.phase() == GamePhase::Playing {
+        game.calculate_score();
+    }
+    game.confirm_score(Turn::Black);
+    game.confirm_score(Turn::White);
+    game
+}
+
+pub fn run_tournament<SpinorT: Spinor>(
+    config: &TournamentConfig,
+    mut output: impl Write,
+) -> io::Result<TournamentStats> {
+    if let Some(dir) = &config.records {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tiling_parameters =
+        TilingParameters::new::<SpinorT>(config.edge_count, config.sides, config.around_vertex);
+    let [first, second] = config.players;
+
+    let mut stats = TournamentStats::default();
+    for g in 0..config.games {
+        let first_is_black = g % 2 == 0;
+        let by_color = if first_is_black {
+            [first, second]
+        } else {
+            [second, first]
+        };
+        let mut rng_state = config.seed.wrapping_add(g as u64);
+        let game = play_game::<SpinorT>(
+            tiling_parameters,
+            by_color,
+            config.rules,
+            &config.search_params,
+            &mut rng_state,
+        );
+        let result = game.result.unwrap();
+        let margin = game
+            .final_score
+            .as_ref()
+            .map_or(0.0, |score| score.black.total - score.white.total);
+        let length = game.moves().len() as u32;
+
+        stats.games += 1;
+        stats.moves += length;
+        stats.margin += if first_is_black { margin } else { -margin };
+        match result.winner {
+            Some(winner) => {
+                let first_won = (winner == Turn::Black) == first_is_black;
+                stats.wins[!first_won as usize] += 1;
+                if winner == Turn::Black {
+                    stats.black_wins += 1;
+                }
+            }
+            None => stats.draws += 1,
+        }
+
+        if let Some(dir) = &config.records {
+            std::fs::write(dir.join(format!("game-{:03}.sgf", g + 1)), game.to_sgf())?;
+        }
+        writeln!(
+            output,
+            "game {}: {} (B) vs {} (W), {} in {} moves",
+            g + 1,
+            by_color[0],
+            by_color[1],
+            result,
+            length
+        )?;
+    }
+
+    for (k, player) in config.players.iter().enumerate() {
+        writeln!(
+            output,
+            "player {} ({}): {}/{} wins, {:.1}%",
+            k + 1,
+            player,
+            stats.wins[k],
+            stats.games,
+            100.0 * stats.win_rate(k)
+        )?;
+    }
+    writeln!(
+        output,
+        "black won {}/{}, {} draws, average margin {:+.1} for player 1, average length {:.1} moves",
+        stats.black_wins,
+        stats.games,
+        stats.draws,
+        stats.average_margin(),
+        stats.average_length()
+    )?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::sgf::sgf_geometry;
+    use crate::geometry::hyperbolic::SpinorHyperbolic;
+
+    #[test]
+    fn test_tournament() {
+        let records = std::env::temp_dir().join(format!("hypergo-test-{}", std::process::id()));
+        let config = TournamentConfig {
+            games: 2,
+            edge_count: 3,
+            sides: 5,
+            around_vertex: 4,
+            players: [Player::Heuristic, Player::Random],
+            rules: Rules::japanese(),
+            search_params: SearchParams::default(),
+            records: Some(records.clone()),
+            seed: 1,
+        };
+        let mut output = Vec::new();
+        let stats = run_tournament::<SpinorHyperbolic>(&config, &mut output).unwrap();
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.wins[0] + stats.wins[1] + stats.draws, 2);
+        assert!(stats.moves > 0);
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("game 1: heuristic (B) vs random (W)"));
+        assert!(output.contains("game 2: random (B) vs heuristic (W)"));
+        for g in 1..=2 {
+            let sgf = std::fs::read_to_string(records.join(format!("game-00{}.sgf", g))).unwrap();
+            assert_eq!(sgf_geometry(&sgf).unwrap(), Geometry::Hyperbolic);
+            assert!(sgf.contains("RE["));
+        }
+        std::fs::remove_dir_all(&records).unwrap();
+
+        // same seed, same games
+        let config = TournamentConfig {
+            records: None,
+            ..config
+        };
+        let again = run_tournament::<SpinorHyperbolic>(&config, io::sink()).unwrap();
+        assert_eq!(again, stats);
+
+        // records of games with suicides in them load again
+        let records = records.with_extension("suicide");
+        let config = TournamentConfig {
+            players: [Player::Random, Player::Random],
+            rules: Rules::new_zealand(),
+            records: Some(records.clone()),
+            ..config
+        };
+        run_tournament::<SpinorHyperbolic>(&config, io::sink()).unwrap();
+        let mut suicides = 0;
+        for g in 1..=2 {
+            let sgf = std::fs::read_to_string(records.join(format!("game-00{}.sgf", g))).unwrap();
+            let game = GameState::<SpinorHyperbolic>::from_sgf(&sgf).unwrap();
+            assert_eq!(game.rules, Rules::new_zealand());
+            assert_eq!(game.to_sgf(), sgf);
+            suicides += game
+                .moves()
+                .iter()
+                .filter(|record| !record.self_captured.is_empty())
+                .count();
+        }
+        assert!(suicides > 0);
+        std::fs::remove_dir_all(records).unwrap();
+    }
+}